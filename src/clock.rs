@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .add_system_to_stage(CoreStage::PreUpdate, tick_clock)
+            .add_system(expire_lifetimes);
+    }
+}
+
+/// Time as seen by the game world: stops while paused and can run slower or faster.
+///
+/// Menus animate on real [`Time`] instead.
+pub struct GameClock {
+    pub paused: bool,
+    pub scale: f32,
+    delta: Duration,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            scale: 1f32,
+            delta: Duration::ZERO,
+        }
+    }
+}
+
+impl GameClock {
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+}
+
+fn tick_clock(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    clock.delta = if clock.paused {
+        Duration::ZERO
+    } else {
+        time.delta().mul_f32(clock.scale.max(0f32))
+    };
+}
+
+/// How long an entity has left, on the game clock.
+#[derive(Component)]
+pub struct Lifetime {
+    pub timer: Timer,
+    despawn: bool,
+}
+
+impl Lifetime {
+    /// Only runs out, for systems that check on it themselves.
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, false),
+            despawn: false,
+        }
+    }
+
+    /// Despawns the entity once over.
+    pub fn despawning(seconds: f32) -> Self {
+        Self {
+            despawn: true,
+            ..Self::new(seconds)
+        }
+    }
+}
+
+fn expire_lifetimes(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    mut q_lifetimes: Query<(Entity, &mut Lifetime)>,
+) {
+    for (entity, mut lifetime) in &mut q_lifetimes {
+        if lifetime.timer.tick(clock.delta()).just_finished() && lifetime.despawn {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,54 +1,114 @@
-use bevy::{prelude::*, render::camera::RenderTarget};
+use bevy::{input::InputSystem, prelude::*, render::camera::RenderTarget};
 
 pub struct CursorPlugin;
 
 impl Plugin for CursorPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(MousePos(Vec2::default()))
-            .add_system(my_cursor_system);
+        app.init_resource::<Pointers>()
+            .add_system_to_stage(CoreStage::PreUpdate, track_pointers.after(InputSystem));
     }
 }
 
 #[derive(Component)]
 pub struct MainCamera;
 
-pub struct MousePos(pub Vec2);
-
-fn my_cursor_system(
-    // need to get window dimensions
-    wnds: Res<Windows>,
-    mut mouse_pos: ResMut<MousePos>,
-    // query to get camera transform
-    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-) {
-    // get the camera info and transform
-    // assuming there is exactly one main camera entity, so query::single() is OK
-    let (camera, camera_transform) = q_camera.single();
-
-    // get the window that the camera is displaying to (or the primary window)
-    let wnd = if let RenderTarget::Window(id) = camera.target {
-        wnds.get(id).unwrap()
-    } else {
-        wnds.get_primary().unwrap()
-    };
+/// World positions of everything pointing at the game, updated before any game system runs.
+///
+/// A pointer is absent while it is outside of every camera's viewport.
+#[derive(Default)]
+pub struct Pointers {
+    pub mouse: Option<Vec2>,
+    /// Touches by id, oldest first.
+    pub touches: Vec<(u64, Vec2)>,
+}
 
-    // check if the cursor is inside the window and get its position
-    if let Some(screen_pos) = wnd.cursor_position() {
-        // get the size of the window
-        let window_size = Vec2::new(wnd.width() as f32, wnd.height() as f32);
+impl Pointers {
+    /// The mouse when it is over the game, otherwise the oldest touch.
+    pub fn primary(&self) -> Option<Vec2> {
+        self.mouse
+            .or_else(|| self.touches.first().map(|(_, position)| *position))
+    }
+}
 
-        // convert screen position [0..resolution] to ndc [-1..1] (gpu coordinates)
-        let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
+/// Flips a position measured from the bottom of the window to be measured from its top.
+fn from_top(window: &Window, position: Vec2) -> Vec2 {
+    Vec2::new(position.x, window.height() - position.y)
+}
 
-        // matrix for undoing the projection and camera transform
-        let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+/// Touches come from the top of the window, except on Android where Bevy flips them to
+/// match the cursor.
+fn touch_from_top(window: &Window, position: Vec2) -> Vec2 {
+    if cfg!(target_os = "android") {
+        from_top(window, position)
+    } else {
+        position
+    }
+}
 
-        // use it to convert ndc to world-space coordinates
-        let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
+/// Converts a window position measured from its top to the world, through the highest
+/// priority camera showing it, the way viewports are measured.
+fn to_world(
+    window: &Window,
+    position: Vec2,
+    q_cameras: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    q_cameras
+        .iter()
+        .filter(|(camera, _)| {
+            camera.is_active
+                && matches!(camera.target, RenderTarget::Window(id) if id == window.id())
+        })
+        .filter_map(|(camera, transform)| {
+            let (min, max) = camera.logical_viewport_rect()?;
+            if position.cmplt(min).any() || position.cmpgt(max).any() || min == max {
+                return None;
+            }
+            let ndc = (position - min) / (max - min) * 2f32 - Vec2::ONE;
+            let ndc_to_world = transform.compute_matrix() * camera.projection_matrix().inverse();
+            let world_pos = ndc_to_world.project_point3(Vec3::new(ndc.x, -ndc.y, -1f32));
+            Some((camera.priority, world_pos.truncate()))
+        })
+        .max_by_key(|(priority, _)| *priority)
+        .map(|(_, world_pos)| world_pos)
+}
 
-        // reduce it to a 2D value
-        let world_pos: Vec2 = world_pos.truncate();
+pub fn track_pointers(
+    windows: Res<Windows>,
+    touches: Res<Touches>,
+    mut pointers: ResMut<Pointers>,
+    q_cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    pointers.mouse = windows.iter().find_map(|window| {
+        // Cursors are measured from the bottom of the window.
+        let screen_pos = window.cursor_position()?;
+        to_world(window, from_top(window, screen_pos), &q_cameras)
+    });
 
-        mouse_pos.0 = world_pos;
+    // Touches are only reported for the primary window.
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => {
+            pointers.touches.clear();
+            return;
+        }
+    };
+    let mut ids: Vec<u64> = pointers
+        .touches
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| touches.get_pressed(*id).is_some())
+        .collect();
+    for touch in touches.iter() {
+        if !ids.contains(&touch.id()) {
+            ids.push(touch.id());
+        }
     }
+    pointers.touches = ids
+        .into_iter()
+        .filter_map(|id| {
+            let touch = touches.get_pressed(id)?;
+            let position = touch_from_top(window, touch.position());
+            Some((id, to_world(window, position, &q_cameras)?))
+        })
+        .collect();
 }
@@ -2,35 +2,25 @@ use bevy::prelude::*;
 use bevy_jornet::Leaderboard;
 
 use crate::{
-    game::{GameResult, Level},
+    game::{GameResult, Level, LevelOutcome},
+    settings::Settings,
+    theme::{Theme, ThemeColor},
+    tween::{Ease, NodeColor, Scale, Tween},
+    ui::{ButtonAction, ButtonPressed, Skin},
     GameState, TextFont,
 };
 
-const BACKGROUND: &str = "43C775";
-const BACKGROUND_CLOSE: &str = "A3A225";
-const BACKGROUND_FAILED: &str = "F31215";
-const BUTTON: &str = "2A4747";
-const TEXT: &str = "BeDaD6";
-
 pub struct DonePlugin;
 impl Plugin for DonePlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Done).with_system(display_menu))
-            .add_system_set(
-                SystemSet::on_update(GameState::Done)
-                    .with_system(button_system_retry)
-                    .with_system(button_system_next),
-            )
+            .add_system_set(SystemSet::on_update(GameState::Done).with_system(handle_buttons))
             .add_system_set(SystemSet::on_exit(GameState::Done).with_system(despawn_menu));
     }
 }
 
 #[derive(Component)]
 struct DoneUI;
-#[derive(Component)]
-struct ButtonNext;
-#[derive(Component)]
-struct ButtonRetry;
 
 fn display_menu(
     mut commands: Commands,
@@ -38,110 +28,69 @@ fn display_menu(
     game_result: Res<GameResult>,
     level: Res<Level>,
     leaderboard: Res<Leaderboard>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
 ) {
-    let difference = (game_result.target_number - game_result.last_number).abs();
-    let is_exact_win = difference == 0f32;
-    let is_close_win = difference <= 0.5f32;
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                margin: UiRect::all(Val::Auto),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                flex_direction: FlexDirection::ColumnReverse,
-                border: UiRect::all(Val::Px(10.0)),
-                ..default()
-            },
-            color: Color::hex(if is_exact_win {
-                BACKGROUND
-            } else if is_close_win {
-                BACKGROUND_CLOSE
-            } else {
-                BACKGROUND_FAILED
-            })
-            .unwrap()
-            .into(),
-            ..default()
-        })
+    let skin = Skin {
+        font: &font,
+        settings: &settings,
+        theme: &theme,
+    };
+    let format = settings.number_format;
+    let outcome = game_result.outcome();
+    let is_exact_win = outcome == LevelOutcome::Won;
+    let is_close_win = outcome == LevelOutcome::Close;
+    let background = match outcome {
+        LevelOutcome::Won => ThemeColor::BackgroundWon,
+        LevelOutcome::Close => ThemeColor::BackgroundClose,
+        LevelOutcome::Failed => ThemeColor::BackgroundFailed,
+    };
+    let button_size = Size::new(Val::Px(350.0), Val::Px(65.0));
+    skin.spawn_panel(&mut commands, background, 10.0)
         .insert(DoneUI)
+        .insert(
+            Tween::<Scale>::from_to(Vec3::splat(0.3), Vec3::ONE, 0.5)
+                .with_ease(Ease::BackOut)
+                .unscaled(),
+        )
+        .insert(
+            Tween::<NodeColor>::from_to(theme.background, theme.color(background), 0.5).unscaled(),
+        )
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle::from_section(
+            skin.label(
+                parent,
                 if is_exact_win {
                     "PERFECT WIN!".to_string()
                 } else if is_close_win {
                     format!(
-                        "Close enough! {:.3} == {}",
-                        game_result.last_number, game_result.target_number
+                        "Close enough! {} == {}",
+                        format.format(game_result.last_number),
+                        format.format(game_result.target_number)
                     )
                 } else {
                     format!(
                         "Target was {} but you had {}",
-                        game_result.target_number,
-                        format!("{:.1}", game_result.last_number).trim_end_matches(".0")
+                        format.format(game_result.target_number),
+                        format.format(game_result.last_number)
                     )
                 },
-                TextStyle {
-                    font: font.0.clone(),
-                    font_size: 50.0,
-                    color: Color::hex(TEXT).unwrap(),
-                },
-            ));
-            parent.spawn_bundle(TextBundle::from_section(
-                format!("Level {}", level.level_index + 1),
-                TextStyle {
-                    font: font.0.clone(),
-                    font_size: 30.0,
-                    color: Color::hex(TEXT).unwrap(),
-                },
-            ));
+                50.0,
+            );
+            skin.label(parent, format!("Level {}", level.level_index + 1), 30.0);
+            skin.label(parent, format!("Time: {:.1}s", game_result.seconds), 30.0);
+            skin.label(parent, format!("Score: {}", game_result.score()), 30.0);
             if is_exact_win || is_close_win {
                 leaderboard.send_score((level.level_index + 1) as f32);
-                parent
-                    .spawn_bundle(ButtonBundle {
-                        style: Style {
-                            size: Size::new(Val::Px(350.0), Val::Px(65.0)),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        color: Color::hex(BUTTON).unwrap().into(),
-                        ..default()
-                    })
-                    .insert(ButtonNext)
-                    .with_children(|parent| {
-                        parent.spawn_bundle(TextBundle::from_section(
-                            "NEXT LEVEL",
-                            TextStyle {
-                                font: font.0.clone(),
-                                font_size: 40.0,
-                                color: Color::hex(TEXT).unwrap(),
-                            },
-                        ));
-                    });
+                skin.button(
+                    parent,
+                    "NEXT LEVEL",
+                    ButtonAction::NextLevel,
+                    button_size,
+                    40.0,
+                );
             }
             if !is_exact_win {
-                parent
-                    .spawn_bundle(ButtonBundle {
-                        style: Style {
-                            size: Size::new(Val::Px(350.0), Val::Px(65.0)),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        color: Color::hex(BUTTON).unwrap().into(),
-                        ..default()
-                    })
-                    .insert(ButtonRetry)
-                    .with_children(|parent| {
-                        parent.spawn_bundle(TextBundle::from_section(
-                            "RETRY",
-                            TextStyle {
-                                font: font.0.clone(),
-                                font_size: 40.0,
-                                color: Color::hex(TEXT).unwrap(),
-                            },
-                        ));
-                    });
+                skin.button(parent, "RETRY", ButtonAction::Retry, button_size, 40.0);
             }
         });
 }
@@ -155,49 +104,21 @@ fn despawn_menu(
     }
 }
 
-fn button_system_retry(
-    mut interaction_query: Query<
-        (&Interaction, &mut UiColor),
-        (Changed<Interaction>, (With<Button>, With<ButtonRetry>)),
-    >,
-    mut state: ResMut<State<GameState>>,
-) {
-    for (interaction, mut color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                *color = (Color::hex(BUTTON).unwrap() + Color::GRAY).into();
-                let _ = state.set(GameState::Menu);
-            }
-            Interaction::Hovered => {
-                *color = (Color::hex(BUTTON).unwrap() + Color::DARK_GRAY).into();
-            }
-            Interaction::None => {
-                *color = Color::hex(BUTTON).unwrap().into();
-            }
-        }
-    }
-}
-fn button_system_next(
+fn handle_buttons(
     mut level: ResMut<Level>,
-    mut interaction_query: Query<
-        (&Interaction, &mut UiColor),
-        (Changed<Interaction>, (With<Button>, With<ButtonNext>)),
-    >,
+    mut pressed: EventReader<ButtonPressed>,
     mut state: ResMut<State<GameState>>,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
+    for ButtonPressed(action) in pressed.iter() {
+        match action {
+            ButtonAction::NextLevel => {
                 level.level_index += 1;
-                *color = (Color::hex(BUTTON).unwrap() + Color::GRAY).into();
                 let _ = state.set(GameState::Menu);
             }
-            Interaction::Hovered => {
-                *color = (Color::hex(BUTTON).unwrap() + Color::DARK_GRAY).into();
-            }
-            Interaction::None => {
-                *color = Color::hex(BUTTON).unwrap().into();
+            ButtonAction::Retry => {
+                let _ = state.set(GameState::Game);
             }
+            _ => {}
         }
     }
 }
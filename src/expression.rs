@@ -0,0 +1,437 @@
+use std::fmt;
+
+use crate::{game::Operation, rules::Arithmetic, solver::Step};
+
+/// Why a typed expression can't be played, and where in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionError {
+    /// Character index of the offending part, which is the length of the input when it ended early.
+    pub position: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedEnd,
+    ExpectedNumber,
+    UnclosedParenthesis,
+    /// The number isn't in the inventory, or not as many times as it is used.
+    UnavailableNumber(String),
+    UnavailableOperation(Operation),
+    Invalid(String),
+    NothingToCombine,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Column {}: ", self.position + 1)?;
+        match &self.kind {
+            ErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected '{c}'"),
+            ErrorKind::UnexpectedEnd => write!(f, "the expression is not finished"),
+            ErrorKind::ExpectedNumber => write!(f, "expected a number or '('"),
+            ErrorKind::UnclosedParenthesis => write!(f, "missing ')'"),
+            ErrorKind::UnavailableNumber(number) => write!(f, "no {number} left to use"),
+            ErrorKind::UnavailableOperation(operation) => {
+                write!(f, "{operation} is not available")
+            }
+            ErrorKind::Invalid(reason) => write!(f, "{reason}"),
+            ErrorKind::NothingToCombine => write!(f, "combine at least two numbers"),
+        }
+    }
+}
+
+/// Turns an expression like `(3+7)*4-2` into the rounds that play it from `numbers`.
+///
+/// Each number in the expression uses up one matching inventory number, and steps are
+/// indexes into the inventory as it is when they are played.
+pub fn plan(
+    input: &str,
+    numbers: &[f32],
+    operations: &[Operation],
+    arithmetic: Arithmetic,
+) -> Result<Vec<Step>, ExpressionError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut parser = Parser {
+        chars: &chars,
+        position: 0,
+    };
+    let node = parser.expression()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(ErrorKind::UnexpectedCharacter(c)));
+    }
+    if let Node::Number { position, .. } = node {
+        return Err(ExpressionError {
+            position,
+            kind: ErrorKind::NothingToCombine,
+        });
+    }
+
+    let mut planner = Planner {
+        slots: numbers
+            .iter()
+            .enumerate()
+            .map(|(id, value)| Slot {
+                id,
+                value: *value,
+                used: false,
+            })
+            .collect(),
+        operations,
+        arithmetic,
+        steps: vec![],
+    };
+    planner.lower(&node)?;
+    Ok(planner.steps)
+}
+
+enum Node {
+    Number {
+        value: f32,
+        text: String,
+        position: usize,
+    },
+    Binary {
+        operation: Operation,
+        position: usize,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn error(&self, kind: ErrorKind) -> ExpressionError {
+        ExpressionError {
+            position: self.position,
+            kind,
+        }
+    }
+
+    /// Sums and differences of terms, left to right.
+    fn expression(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.term()?;
+        loop {
+            self.skip_whitespace();
+            let operation = match self.peek() {
+                Some('+') => Operation::Plus,
+                Some('-') => Operation::Minus,
+                _ => return Ok(node),
+            };
+            node = self.binary(node, operation, Self::term)?;
+        }
+    }
+
+    /// Products and quotients of factors, left to right.
+    fn term(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            let operation = match self.peek() {
+                Some('*' | 'x' | '×') => Operation::Multiply,
+                Some('/' | '÷') => Operation::Divide,
+                _ => return Ok(node),
+            };
+            node = self.binary(node, operation, Self::factor)?;
+        }
+    }
+
+    fn binary(
+        &mut self,
+        left: Node,
+        operation: Operation,
+        operand: fn(&mut Self) -> Result<Node, ExpressionError>,
+    ) -> Result<Node, ExpressionError> {
+        let position = self.position;
+        self.position += 1;
+        let right = operand(self)?;
+        Ok(Node::Binary {
+            operation,
+            position,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    fn factor(&mut self) -> Result<Node, ExpressionError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(self.error(ErrorKind::UnexpectedEnd)),
+            Some('(') => {
+                let open = self.position;
+                self.position += 1;
+                let node = self.expression()?;
+                self.skip_whitespace();
+                if self.peek() == Some(')') {
+                    self.position += 1;
+                    Ok(node)
+                } else if self.peek().is_none() {
+                    Err(ExpressionError {
+                        position: open,
+                        kind: ErrorKind::UnclosedParenthesis,
+                    })
+                } else {
+                    Err(self.error(ErrorKind::UnclosedParenthesis))
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let position = self.position;
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+                    self.position += 1;
+                }
+                let text: String = self.chars[position..self.position].iter().collect();
+                match text.parse::<f32>() {
+                    Ok(value) => Ok(Node::Number {
+                        value,
+                        text,
+                        position,
+                    }),
+                    Err(_) => Err(ExpressionError {
+                        position,
+                        kind: ErrorKind::ExpectedNumber,
+                    }),
+                }
+            }
+            Some(_) => Err(self.error(ErrorKind::ExpectedNumber)),
+        }
+    }
+}
+
+/// An inventory number, followed through the rounds by its original index.
+struct Slot {
+    id: usize,
+    value: f32,
+    used: bool,
+}
+
+struct Planner<'a> {
+    slots: Vec<Slot>,
+    operations: &'a [Operation],
+    arithmetic: Arithmetic,
+    steps: Vec<Step>,
+}
+
+impl Planner<'_> {
+    fn index(&self, id: usize) -> usize {
+        self.slots
+            .iter()
+            .position(|slot| slot.id == id)
+            .expect("slots only leave once combined into another")
+    }
+
+    /// Plays the node and returns the id of the slot holding its value.
+    fn lower(&mut self, node: &Node) -> Result<usize, ExpressionError> {
+        match node {
+            Node::Number {
+                value,
+                text,
+                position,
+            } => {
+                let slot = self
+                    .slots
+                    .iter_mut()
+                    .find(|slot| !slot.used && slot.value == *value)
+                    .ok_or_else(|| ExpressionError {
+                        position: *position,
+                        kind: ErrorKind::UnavailableNumber(text.clone()),
+                    })?;
+                slot.used = true;
+                Ok(slot.id)
+            }
+            Node::Binary {
+                operation,
+                position,
+                left,
+                right,
+            } => {
+                if !self.operations.contains(operation) {
+                    return Err(ExpressionError {
+                        position: *position,
+                        kind: ErrorKind::UnavailableOperation(operation.clone()),
+                    });
+                }
+                let left = self.lower(left)?;
+                let right = self.lower(right)?;
+                let (first, second) = (self.index(left), self.index(right));
+                let result = operation
+                    .apply(
+                        self.slots[first].value,
+                        self.slots[second].value,
+                        self.arithmetic,
+                    )
+                    .map_err(|reason| ExpressionError {
+                        position: *position,
+                        kind: ErrorKind::Invalid(reason.to_string()),
+                    })?;
+                self.slots[first].value = result;
+                self.slots.remove(second);
+                self.steps.push(Step {
+                    first,
+                    second,
+                    operation: operation.clone(),
+                });
+                Ok(left)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_OPERATIONS: [Operation; 4] = [
+        Operation::Plus,
+        Operation::Minus,
+        Operation::Multiply,
+        Operation::Divide,
+    ];
+
+    fn step(first: usize, second: usize, operation: Operation) -> Step {
+        Step {
+            first,
+            second,
+            operation,
+        }
+    }
+
+    fn error(position: usize, kind: ErrorKind) -> Result<Vec<Step>, ExpressionError> {
+        Err(ExpressionError { position, kind })
+    }
+
+    fn plan_all(input: &str, numbers: &[f32]) -> Result<Vec<Step>, ExpressionError> {
+        plan(input, numbers, &ALL_OPERATIONS, Arithmetic::Fractions)
+    }
+
+    #[test]
+    fn multiplies_before_adding() {
+        assert_eq!(
+            plan_all("3+7*4", &[3f32, 7f32, 4f32]),
+            Ok(vec![
+                step(1, 2, Operation::Multiply),
+                step(0, 1, Operation::Plus)
+            ])
+        );
+    }
+
+    #[test]
+    fn parentheses_come_first() {
+        assert_eq!(
+            plan_all("(3+7)*4", &[3f32, 7f32, 4f32]),
+            Ok(vec![
+                step(0, 1, Operation::Plus),
+                step(0, 1, Operation::Multiply)
+            ])
+        );
+    }
+
+    #[test]
+    fn steps_index_the_inventory_as_it_is_when_played() {
+        assert_eq!(
+            plan_all("5 * (3 - 2)", &[2f32, 5f32, 3f32]),
+            Ok(vec![
+                step(2, 0, Operation::Minus),
+                step(0, 1, Operation::Multiply)
+            ])
+        );
+    }
+
+    #[test]
+    fn numbers_are_used_as_many_times_as_they_are_dealt() {
+        assert_eq!(
+            plan_all("3+3", &[3f32, 3f32, 4f32]),
+            Ok(vec![step(0, 1, Operation::Plus)])
+        );
+        assert_eq!(
+            plan_all("3+3", &[3f32, 4f32]),
+            error(2, ErrorKind::UnavailableNumber("3".to_string()))
+        );
+    }
+
+    #[test]
+    fn missing_numbers_are_refused() {
+        assert_eq!(
+            plan_all("3 + 9", &[3f32, 4f32]),
+            error(4, ErrorKind::UnavailableNumber("9".to_string()))
+        );
+    }
+
+    #[test]
+    fn unavailable_operations_are_refused() {
+        assert_eq!(
+            plan(
+                "3*4",
+                &[3f32, 4f32],
+                &[Operation::Plus],
+                Arithmetic::Fractions
+            ),
+            error(1, ErrorKind::UnavailableOperation(Operation::Multiply))
+        );
+    }
+
+    #[test]
+    fn rounds_follow_the_arithmetic() {
+        assert_eq!(
+            plan("7/2", &[7f32, 2f32], &ALL_OPERATIONS, Arithmetic::Fractions),
+            Ok(vec![step(0, 1, Operation::Divide)])
+        );
+        assert_eq!(
+            plan("7/2", &[7f32, 2f32], &ALL_OPERATIONS, Arithmetic::Integers),
+            error(1, ErrorKind::Invalid("Doesn't divide exactly".to_string()))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_character() {
+        let numbers = [3f32, 4f32];
+        assert_eq!(plan_all("3+", &numbers), error(2, ErrorKind::UnexpectedEnd));
+        assert_eq!(
+            plan_all("3 + )", &numbers),
+            error(4, ErrorKind::ExpectedNumber)
+        );
+        assert_eq!(
+            plan_all("3 4", &numbers),
+            error(2, ErrorKind::UnexpectedCharacter('4'))
+        );
+        assert_eq!(
+            plan_all("(3+4", &numbers),
+            error(0, ErrorKind::UnclosedParenthesis)
+        );
+        assert_eq!(
+            plan_all("(3+4 4", &numbers),
+            error(5, ErrorKind::UnclosedParenthesis)
+        );
+        assert_eq!(
+            plan_all("1..2+3", &numbers),
+            error(0, ErrorKind::ExpectedNumber)
+        );
+        assert_eq!(
+            plan_all("3", &numbers),
+            error(0, ErrorKind::NothingToCombine)
+        );
+    }
+
+    #[test]
+    fn errors_read_with_a_column() {
+        let error = plan_all("3+", &[3f32, 4f32]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Column 3: the expression is not finished"
+        );
+    }
+}
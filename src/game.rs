@@ -1,10 +1,40 @@
-use crate::{cursor::MousePos, particles::ParticleExplosion, GameState, TextFont};
+use crate::{
+    clock::{GameClock, Lifetime},
+    expression::{self, ExpressionError},
+    input::{self, ActionPressed, InputAction},
+    layout::BoardLayout,
+    particles::{ParticleEffect, ParticleExplosion},
+    picking::{Hover, Pickable},
+    random::{self, Pcg32},
+    rules::{Arithmetic, GameMode, Rules},
+    settings::Settings,
+    solver,
+    theme::{Theme, ThemeColor, Themed},
+    tween::{
+        Ease, Rotation, Scale, SpriteColor, TextAlpha, Translation, Tween, TweenFinished, TweenLens,
+    },
+    ui::{ButtonAction, ButtonPressed, Disabled, Skin},
+    GameState, TextFont,
+};
+use std::collections::VecDeque;
+
 use bevy::{
     input::{mouse::MouseButtonInput, ButtonState},
     math::Vec3Swizzles,
     prelude::*,
+    time::Stopwatch,
+    window::ReceivedCharacter,
 };
-use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rand::Rng;
+
+const MERGE_SECONDS: f32 = 0.2;
+const POP_SECONDS: f32 = 0.25;
+const LAYOUT_SECONDS: f32 = 0.3;
+const SHAKE_STEP_SECONDS: f32 = 0.06;
+const TOOLTIP_SECONDS: f32 = 1.5;
+/// How long a second restart press confirms the first one.
+const RESTART_CONFIRM_SECONDS: f64 = 2.0;
+const TOOLTIP_FADE_SECONDS: f32 = 0.5;
 
 pub struct GamePlugin;
 
@@ -13,33 +43,171 @@ impl Plugin for GamePlugin {
         app.insert_resource(Level {
             seed: rand::thread_rng().gen_range(u64::MIN..=u64::MAX),
             level_index: 0,
+            arithmetic: Arithmetic::Fractions,
         });
         app.insert_resource(GameResult::default());
+        app.insert_resource(GameMode::Classic.rules(Arithmetic::Fractions));
+        app.add_event::<GameEvent>()
+            .add_event::<GameAction>()
+            .add_event::<RestartLevel>()
+            .add_event::<SubmitLevel>()
+            .add_system(particles_on_game_events)
+            .add_system(finish_merges);
         app.add_system_set(SystemSet::on_enter(GameState::Game).with_system(new_game))
             .add_system_set(
                 SystemSet::on_update(GameState::Game)
-                    .with_system(update_inventory)
-                    .with_system(handle_clicks)
-                    .with_system(react_play_round)
-                    .with_system(visibility_selection),
+                    // Sees the slots once merges have been applied.
+                    .with_system(update_inventory.before(react_play_round))
+                    .with_system(mouse_actions)
+                    .with_system(keyboard_actions)
+                    .with_system(bound_actions)
+                    .with_system(
+                        apply_actions
+                            .after(mouse_actions)
+                            .after(keyboard_actions)
+                            .after(bound_actions),
+                    )
+                    .with_system(update_focus_marker.after(apply_actions))
+                    .with_system(update_preview.after(react_play_round))
+                    .with_system(update_badges.after(react_play_round))
+                    .with_system(update_expression.after(react_play_round))
+                    .with_system(restyle_board.after(update_inventory))
+                    .with_system(handle_buttons)
+                    .with_system(tick_level_time)
+                    .with_system(selection_feedback)
+                    .with_system(confirm_restart.after(hold_actions_when_over))
+                    .with_system(restart_level.after(apply_actions).after(confirm_restart))
+                    .with_system(react_play_round.after(apply_actions))
+                    .with_system(end_level.after(react_play_round))
+                    .with_system(leave_finished_level.after(finish_merges))
+                    .with_system(
+                        hold_actions_when_over
+                            .after(mouse_actions)
+                            .after(keyboard_actions)
+                            .after(bound_actions)
+                            .after(handle_buttons)
+                            .before(apply_actions),
+                    )
+                    .with_system(type_expression.after(keyboard_actions).after(bound_actions))
+                    .with_system(show_expression_input.after(type_expression))
+                    .with_system(
+                        play_pending_steps
+                            .after(apply_actions)
+                            .before(react_play_round),
+                    )
+                    .with_system(visibility_selection.after(react_play_round))
+                    .with_system(search_hint.after(react_play_round))
+                    .with_system(relayout_board),
             )
-            .add_system_set(SystemSet::on_exit(GameState::Game).with_system(despawn_game));
+            .add_system_set(SystemSet::on_exit(GameState::Game).with_system(despawn_game))
+            .add_system_set(SystemSet::on_pause(GameState::Game).with_system(disable_board))
+            .add_system_set(
+                SystemSet::on_resume(GameState::Game)
+                    .with_system(enable_board)
+                    .with_system(clear_stale_input)
+                    .with_system(input::clear_actions),
+            );
     }
 }
 
+/// Everything a puzzle is generated from, so that sharing it gives the same puzzle anywhere.
 pub struct Level {
     seed: u64,
     pub level_index: u64,
+    pub arithmetic: Arithmetic,
 }
 
 #[derive(Default)]
 pub struct GameResult {
     pub last_number: f32,
     pub target_number: f32,
+    pub seconds: f32,
+    /// Numbers left besides the last one, when the rules allow finishing early.
+    pub unused_numbers: usize,
+    pub unused_number_points: i32,
+}
+
+impl GameResult {
+    /// Unused numbers only count towards a won or close result, so giving up early earns nothing.
+    pub fn score(&self) -> i32 {
+        let unused = self.unused_numbers as i32 * self.unused_number_points;
+        match self.outcome() {
+            LevelOutcome::Won => 100 + unused,
+            LevelOutcome::Close => 50 + unused,
+            LevelOutcome::Failed => 0,
+        }
+    }
+
+    pub fn outcome(&self) -> LevelOutcome {
+        let difference = (self.target_number - self.last_number).abs();
+        if difference == 0f32 {
+            LevelOutcome::Won
+        } else if difference <= 0.5f32 {
+            LevelOutcome::Close
+        } else {
+            LevelOutcome::Failed
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelOutcome {
+    Won,
+    Close,
+    Failed,
+}
+
+/// Notable things happening during a level, for audio and visual feedback to react to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    NumberSelected,
+    OperationSelected,
+    Deselected,
+    /// The first and second selected numbers were exchanged.
+    Swapped,
+    Combined,
+    /// Refused round, at the operation that was tried.
+    InvalidOperation {
+        reason: OperationError,
+        location: Vec2,
+    },
+    /// A selection was refused because there is no room left for it.
+    SelectionFull,
+    /// Submit was pressed before any round made a number.
+    NothingToSubmit,
+    /// Restart was pressed once, and wipes the progress if pressed again soon.
+    RestartRequested,
+    Undone,
+    LevelFinished(LevelOutcome),
 }
 
+/// What the player wants to do, produced by every input device and consumed by
+/// [`apply_actions`], so that they are interchangeable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameAction {
+    /// Toggles the selection of the number at this inventory index.
+    SelectSlot(usize),
+    SelectOperation(Operation),
+    Deselect,
+    Confirm,
+    /// Exchanges the first and second selected numbers.
+    Swap,
+    Undo,
+    Hint,
+    Pause,
+    Restart,
+    /// Locks in the closest number made so far.
+    Submit,
+}
+
+/// Starts the current level over, without leaving the game.
+pub struct RestartLevel;
+
+/// Ends the level with the closest number, when the rules allow it.
+struct SubmitLevel;
+
 #[derive(Component, Debug, PartialEq, Clone)]
-enum Operation {
+pub enum Operation {
     Plus,
     Minus,
     Multiply,
@@ -63,14 +231,19 @@ impl std::fmt::Display for Operation {
 }
 
 impl Operation {
-    fn apply(&self, n1: f32, n2: f32) -> Result<f32, ()> {
+    pub fn apply(&self, n1: f32, n2: f32, arithmetic: Arithmetic) -> Result<f32, OperationError> {
         match self {
             Operation::Plus => Ok(n1 + n2),
+            Operation::Minus if n1 < n2 && !arithmetic.allows_negative() => {
+                Err(OperationError::NegativeResult)
+            }
             Operation::Minus => Ok(n1 - n2),
             Operation::Multiply => Ok(n1 * n2),
             Operation::Divide => {
                 if n2 == 0f32 {
-                    Err(())
+                    Err(OperationError::DivisionByZero)
+                } else if arithmetic.exact_division() && n1 % n2 != 0f32 {
+                    Err(OperationError::InexactDivision)
                 } else {
                     Ok(n1 / n2)
                 }
@@ -79,6 +252,27 @@ impl Operation {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationError {
+    DivisionByZero,
+    InexactDivision,
+    NegativeResult,
+}
+
+impl std::fmt::Display for OperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OperationError::DivisionByZero => "Can't divide by zero",
+                OperationError::InexactDivision => "Doesn't divide exactly",
+                OperationError::NegativeResult => "Can't go below zero",
+            }
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 struct PlayingNumber {
     pub entity: Entity,
@@ -90,6 +284,8 @@ struct PlayRound {
     pub operation: Option<Operation>,
     pub number1: Option<PlayingNumber>,
     pub number2: Option<PlayingNumber>,
+    /// Set by the player in careful mode once the round is complete.
+    pub confirmed: bool,
 }
 
 impl PlayRound {
@@ -97,6 +293,11 @@ impl PlayRound {
         self.operation = None;
         self.number1 = None;
         self.number2 = None;
+        self.confirmed = false;
+    }
+
+    fn is_complete(&self) -> bool {
+        self.operation.is_some() && self.number1.is_some() && self.number2.is_some()
     }
 }
 
@@ -104,8 +305,20 @@ struct TargetNumber {
     pub target: f32,
 }
 
+#[derive(Clone)]
 struct Inventory {
     pub numbers: Vec<f32>,
+    /// Whether each number is the result of a round, rather than dealt.
+    pub made: Vec<bool>,
+}
+
+impl Inventory {
+    fn dealt(numbers: Vec<f32>) -> Self {
+        Self {
+            made: vec![false; numbers.len()],
+            numbers,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -113,9 +326,92 @@ struct InventorySlot {
     inventory_index: usize,
 }
 
+/// Position of an operation in its row.
+#[derive(Component)]
+struct OperationSlot {
+    index: usize,
+}
+
+#[derive(Component)]
+struct TargetText;
+
+/// The round being put together, written out so the order of the numbers is clear.
+/// Clicked to swap them.
+#[derive(Component)]
+struct ExpressionText;
+
+/// Marks the first and second selected numbers, as a child of their slot.
+#[derive(Component)]
+struct OrdinalBadge;
+
+/// Would-be result of the round being put together, clicked to confirm it in careful mode.
+#[derive(Component)]
+struct ResultPreview;
+
 #[derive(Component)]
 struct SelectionVisual(pub Entity);
 
+/// Inventories before each combination, most recent last.
+#[derive(Default)]
+struct History(Vec<Inventory>);
+
+/// Slot or operation highlighted by gamepad navigation.
+#[derive(Default)]
+struct BoardFocus(Option<Entity>);
+
+#[derive(Component)]
+struct FocusMarker;
+
+#[derive(Component)]
+struct Tooltip;
+
+/// A number sliding onto the slot holding the result of its combination.
+#[derive(Component)]
+struct MergingInto(Entity);
+
+/// Expression typed by the player, played as a sequence of rounds once submitted.
+#[derive(Default)]
+struct ExpressionInput {
+    active: bool,
+    text: String,
+    error: Option<ExpressionError>,
+}
+
+/// Rounds left to play from a submitted expression.
+#[derive(Default)]
+struct PendingSteps(VecDeque<solver::Step>);
+
+/// Hint being searched for, a slice per frame.
+#[derive(Default)]
+struct HintSearch(Option<solver::Search>);
+
+/// How many positions a hint search explores each frame.
+const HINT_POSITIONS_PER_FRAME: usize = 2_000;
+
+/// Set once the result is decided, while the last merge plays out before the done screen.
+#[derive(Default)]
+struct LevelOver(bool);
+
+/// Clicked to start typing an expression.
+#[derive(Component)]
+struct ExpressionBox;
+
+#[derive(Component)]
+struct ExpressionInputText;
+
+#[derive(Component)]
+struct ExpressionErrorText;
+
+/// Time spent playing the level, not counting pauses.
+#[derive(Default)]
+struct LevelTime(Stopwatch);
+
+#[derive(Component)]
+struct PauseButton;
+
+#[derive(Component)]
+struct SubmitButton;
+
 #[derive(Component)]
 struct GameEntity;
 
@@ -132,8 +428,15 @@ fn lerp(start_value: f32, end_value: f32, ratio: f32) -> f32 {
     start_value + (end_value - start_value) * ratio
 }
 
-fn new_game(mut commands: Commands, level: Res<Level>, font: Res<TextFont>) {
-    let mut rand = SmallRng::seed_from_u64(level.seed.wrapping_add(level.level_index));
+/// Starting point of a level, always the same for a given [`Level`].
+struct Puzzle {
+    numbers: Vec<f32>,
+    operations: Vec<Operation>,
+    target: f32,
+}
+
+fn generate_puzzle(level: &Level) -> Puzzle {
+    let mut rand = Pcg32::new(random::level_seed(level.seed, level.level_index), 0);
     let mut numbers = vec![];
 
     let number_count = usize::clamp(
@@ -141,14 +444,9 @@ fn new_game(mut commands: Commands, level: Res<Level>, font: Res<TextFont>) {
         2,
         10,
     );
-    let number_range = 1..=10;
-
     for _ in 0..number_count {
-        numbers.push(rand.gen_range(number_range.clone()));
+        numbers.push(rand.between(1, 10));
     }
-    commands.insert_resource(Inventory {
-        numbers: numbers.iter().map(|n| *n as f32).collect(),
-    });
 
     let mut operations = vec![
         Operation::Plus,
@@ -164,60 +462,242 @@ fn new_game(mut commands: Commands, level: Res<Level>, font: Res<TextFont>) {
     operations = operations.drain(..operation_count).collect();
 
     let mut numbers_to_simulate: Vec<f32> = numbers.iter().map(|v| *v as f32).collect();
-    use rand::seq::SliceRandom;
     while numbers_to_simulate.len() > 1 {
-        let chosen_indexes: Vec<usize> = (0..numbers_to_simulate.len())
-            .collect::<Vec<usize>>()
-            .choose_multiple(&mut rand, 2)
-            .cloned()
-            .collect();
-        if let Ok(new_number) = operations.choose(&mut rand).unwrap().apply(
-            numbers_to_simulate[chosen_indexes[0]],
-            numbers_to_simulate[chosen_indexes[1]],
+        let (first, second) = rand.two_indexes(numbers_to_simulate.len());
+        if let Ok(new_number) = operations[rand.index(operations.len())].apply(
+            numbers_to_simulate[first],
+            numbers_to_simulate[second],
+            level.arithmetic,
         ) {
-            numbers_to_simulate[chosen_indexes[0]] = new_number;
-            numbers_to_simulate.remove(chosen_indexes[1]);
+            numbers_to_simulate[first] = new_number;
+            numbers_to_simulate.remove(second);
         }
     }
     // TODO: simulate operations + end up on a doable target
     let target = numbers_to_simulate[0];
 
-    commands.insert_resource(TargetNumber {
-        target: target as f32,
-    });
+    Puzzle {
+        numbers: numbers.iter().map(|n| *n as f32).collect(),
+        operations,
+        target,
+    }
+}
+
+fn new_game(
+    mut commands: Commands,
+    mut level: ResMut<Level>,
+    layout: Res<BoardLayout>,
+    font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    mut rules: ResMut<Rules>,
+) {
+    // Kept for the whole level, even if the settings change from the pause menu.
+    *rules = settings.rules();
+    level.arithmetic = rules.arithmetic;
+    let skin = Skin {
+        font: &font,
+        settings: &settings,
+        theme: &theme,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            skin.button(
+                parent,
+                "II",
+                ButtonAction::Pause,
+                Size::new(Val::Px(65.0), Val::Px(65.0)),
+                40.0,
+            )
+            .insert(PauseButton);
+        });
+    if rules.can_submit {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(10.0),
+                        left: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                ..default()
+            })
+            .insert(GameEntity)
+            .with_children(|parent| {
+                skin.button(
+                    parent,
+                    "Submit",
+                    ButtonAction::Submit,
+                    Size::new(Val::Px(160.0), Val::Px(65.0)),
+                    40.0,
+                )
+                .insert(SubmitButton);
+            });
+    }
+
+    let Puzzle {
+        numbers,
+        operations,
+        target,
+    } = generate_puzzle(&level);
+    commands.insert_resource(Inventory::dealt(numbers));
+    commands.insert_resource(TargetNumber { target });
     commands.insert_resource(PlayRound {
         operation: None,
         number1: None,
         number2: None,
+        confirmed: false,
     });
+    commands.insert_resource(History::default());
+    commands.insert_resource(BoardFocus::default());
+    commands.insert_resource(LevelTime::default());
+    commands.insert_resource(ExpressionInput::default());
+    commands.insert_resource(PendingSteps::default());
+    commands.insert_resource(HintSearch::default());
+    commands.insert_resource(LevelOver::default());
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(0.0),
+                    ..default()
+                },
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: settings.font_size(20.0),
+                        color: theme.background_failed,
+                    },
+                ))
+                .insert(ExpressionErrorText);
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        min_size: Size::new(Val::Px(360.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    color: theme.button.into(),
+                    ..default()
+                })
+                .insert(ExpressionBox)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: settings.font_size(28.0),
+                                color: theme.text,
+                            },
+                        ))
+                        .insert(ExpressionInputText);
+                });
+        });
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(70f32)),
+                ..default()
+            },
+            transform: Transform::from_translation(Vec3::new(0f32, 0f32, 10f32)),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(FocusMarker)
+        .insert(GameEntity);
 
     let text_alignment = TextAlignment::CENTER;
     let text_style = TextStyle {
         font: font.0.clone(),
-        font_size: 60.0,
-        color: Color::WHITE,
+        font_size: settings.font_size(60.0),
+        color: theme.board_text,
     };
     commands
         .spawn_bundle(Text2dBundle {
-            text: Text::from_section(format!("Target: {target}"), text_style.clone())
-                .with_alignment(text_alignment),
-            transform: Transform::from_translation(Vec3::new(0f32, 300f32, 20f32)),
+            text: Text::from_section(
+                format!("Target: {}", settings.number_format.format(target)),
+                text_style.clone(),
+            )
+            .with_alignment(text_alignment),
+            transform: Transform::from_translation(layout.target.extend(20f32)),
+            ..default()
+        })
+        .insert(TargetText)
+        .insert(GameEntity)
+        .insert(Themed(ThemeColor::BoardText));
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: settings.font_size(24.0),
+                    ..text_style.clone()
+                },
+            )
+            .with_alignment(text_alignment),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(ResultPreview)
+        .insert(Pickable::default())
+        .insert(GameEntity);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::default().with_alignment(text_alignment),
+            transform: Transform::from_translation(layout.expression.extend(20f32)),
             ..default()
         })
+        .insert(ExpressionText)
+        .insert(Pickable::default())
         .insert(GameEntity);
 
-    let spacing = 100f32;
-    let offset = Vec2::new(-((operations.len() - 1) as f32 * spacing) / 2f32, -50f32);
     for (x, op) in operations.iter().enumerate() {
         let mut op_commands = commands.spawn_bundle(Text2dBundle {
             text: Text::from_section(format!("{op}"), text_style.clone())
                 .with_alignment(text_alignment),
-            transform: Transform::from_translation(
-                (Vec2::new(x as f32 * spacing, 0f32) + offset).extend(20f32),
-            ),
+            transform: Transform::from_translation(layout.operation_position(x, operations.len())),
             ..default()
         });
-        op_commands.insert(op.clone());
+        op_commands
+            .insert(op.clone())
+            .insert(OperationSlot { index: x })
+            .insert(Pickable {
+                min_size: Vec2::splat(80f32),
+            });
 
         let mut visual_entity = None;
         op_commands.with_children(|parent| {
@@ -225,7 +705,7 @@ fn new_game(mut commands: Commands, level: Res<Level>, font: Res<TextFont>) {
                 parent
                     .spawn_bundle(SpriteBundle {
                         sprite: Sprite {
-                            color: Color::YELLOW_GREEN,
+                            color: theme.selection_hover,
                             custom_size: Some(Vec2::splat(50f32)),
                             ..default()
                         },
@@ -244,218 +724,1419 @@ fn new_game(mut commands: Commands, level: Res<Level>, font: Res<TextFont>) {
     }
 }
 
+/// Shows what the round would give, when it is complete or would be with the hovered entity.
+#[allow(clippy::too_many_arguments)]
+fn update_preview(
+    play_round: Res<PlayRound>,
+    inventory: Res<Inventory>,
+    hover: Res<Hover>,
+    settings: Res<Settings>,
+    rules: Res<Rules>,
+    theme: Res<Theme>,
+    q_inventory_slots: Query<(&InventorySlot, &Transform), Without<ResultPreview>>,
+    q_operations: Query<&Operation>,
+    mut q_preview: Query<(&mut Text, &mut Transform, &mut Visibility), With<ResultPreview>>,
+) {
+    let hovered_slot = hover
+        .0
+        .filter(|hovered| !play_round.number1.iter().any(|n| n.entity == *hovered))
+        .and_then(|hovered| q_inventory_slots.get(hovered).ok());
+    let hovered_operation = hover.0.and_then(|hovered| q_operations.get(hovered).ok());
+    let candidate = match &*play_round {
+        PlayRound {
+            operation: Some(operation),
+            number1: Some(n1),
+            number2: Some(n2),
+            ..
+        } => Some((operation, n1.inventory_index, n2.inventory_index)),
+        PlayRound {
+            operation: None,
+            number1: Some(n1),
+            number2: Some(n2),
+            ..
+        } => hovered_operation.map(|operation| (operation, n1.inventory_index, n2.inventory_index)),
+        PlayRound {
+            operation: Some(operation),
+            number1: Some(n1),
+            number2: None,
+            ..
+        } => hovered_slot.map(|(slot, _)| (operation, n1.inventory_index, slot.inventory_index)),
+        _ => None,
+    };
+    let anchor = play_round
+        .number1
+        .as_ref()
+        .and_then(|n1| q_inventory_slots.get(n1.entity).ok())
+        .map(|(_, transform)| transform.translation);
+
+    for (mut text, mut transform, mut visibility) in &mut q_preview {
+        let numbers = candidate.map(|(_, first, second)| {
+            (inventory.numbers.get(first), inventory.numbers.get(second))
+        });
+        let (operation, a, b, anchor) = match (candidate, numbers, anchor) {
+            (Some((operation, ..)), Some((Some(a), Some(b))), Some(anchor)) => {
+                (operation, *a, *b, anchor)
+            }
+            _ => {
+                visibility.is_visible = false;
+                continue;
+            }
+        };
+        let (value, color) = match operation.apply(a, b, rules.arithmetic) {
+            Ok(result) if result.fract() != 0f32 => (
+                format!("= {}", settings.number_format.format(result)),
+                theme.background_close,
+            ),
+            Ok(result) => (
+                format!("= {}", settings.number_format.format(result)),
+                theme.board_text,
+            ),
+            Err(reason) => (reason.to_string(), theme.background_failed),
+        };
+        let value = if settings.careful_mode && play_round.is_complete() {
+            format!("{value}  (confirm)")
+        } else {
+            value
+        };
+        if text.sections[0].value != value || text.sections[0].style.color != color {
+            text.sections[0].value = value;
+            text.sections[0].style.color = color;
+        }
+        let position = anchor + Vec3::new(0f32, 45f32, 10f32);
+        if transform.translation != position {
+            transform.translation = position;
+        }
+        visibility.is_visible = true;
+    }
+}
+
+/// Moves the board to where the layout wants it after the window changed shape.
+fn relayout_board(
+    mut commands: Commands,
+    layout: Res<BoardLayout>,
+    q_inventory_slots: Query<(Entity, &InventorySlot)>,
+    q_operations: Query<(Entity, &OperationSlot)>,
+    mut q_labels: Query<
+        (&mut Transform, Option<&Tooltip>, Option<&ExpressionText>),
+        Or<(With<TargetText>, With<Tooltip>, With<ExpressionText>)>,
+    >,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+    for (e, slot) in &q_inventory_slots {
+        commands.entity(e).insert(Tween::<Translation>::to(
+            layout.slot_position(slot.inventory_index),
+            LAYOUT_SECONDS,
+        ));
+    }
+    let operation_count = q_operations.iter().count();
+    for (e, slot) in &q_operations {
+        commands.entity(e).insert(Tween::<Translation>::to(
+            layout.operation_position(slot.index, operation_count),
+            LAYOUT_SECONDS,
+        ));
+    }
+    for (mut transform, tooltip, expression) in &mut q_labels {
+        let position = if tooltip.is_some() {
+            layout.tooltip
+        } else if expression.is_some() {
+            layout.expression
+        } else {
+            layout.target
+        };
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+/// Keeps one slot per inventory number, easing them into their place in the grid.
 fn update_inventory(
     mut commands: Commands,
     font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    layout: Res<BoardLayout>,
     inventory: Res<Inventory>,
-    q_inventory_slots: Query<Entity, With<InventorySlot>>,
+    mut q_inventory_slots: Query<(Entity, &InventorySlot, &Transform, &mut Text)>,
 ) {
-    if inventory.is_changed() {
-        for e in q_inventory_slots.iter() {
-            commands.entity(e).despawn_recursive();
-        }
-        let text_alignment = TextAlignment::CENTER;
-        let text_style = TextStyle {
-            font: font.0.clone(),
-            font_size: 60.0,
-            color: Color::WHITE,
-        };
-        let column_count = 5;
-        let spacing = 100f32;
-        let offset = Vec2::new(-((column_count - 1) as f32 * spacing) / 2f32, 200f32);
-        for (i, number) in inventory.numbers.iter().enumerate() {
-            let x = i % column_count;
-            let y = i / column_count;
-            let mut slot = commands.spawn_bundle(Text2dBundle {
-                text: Text::from_section(
-                    format!("{number:.1}").trim_end_matches(".0"),
-                    text_style.clone(),
-                )
-                .with_alignment(text_alignment),
-                transform: Transform::from_translation(
-                    (Vec2::new(x as f32 * spacing, -(y as f32) * spacing) + offset).extend(20f32),
-                ),
-                ..default()
-            });
-
-            slot.insert(InventorySlot { inventory_index: i });
-            let slot_entity = slot.id();
-            let mut visual_entity = None;
-            slot.with_children(|parent| {
-                visual_entity = Some(
-                    parent
-                        .spawn_bundle(SpriteBundle {
-                            sprite: Sprite {
-                                color: Color::YELLOW_GREEN,
-                                custom_size: Some(Vec2::splat(50f32)),
-                                ..default()
-                            },
-                            transform: Transform::from_translation(Vec2::ZERO.extend(-1f32)),
-                            visibility: Visibility { is_visible: false },
-                            ..default()
-                        })
-                        .id(),
+    if !inventory.is_changed() {
+        return;
+    }
+    let mut missing: Vec<usize> = (0..inventory.numbers.len()).collect();
+    for (e, slot, transform, mut text) in &mut q_inventory_slots {
+        if let Some(number) = inventory.numbers.get(slot.inventory_index) {
+            missing.retain(|index| *index != slot.inventory_index);
+            let value = settings.number_format.format(*number);
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+            let position = layout.slot_position(slot.inventory_index);
+            if transform.translation != position {
+                // Wait for merging numbers to reach each other before making room.
+                commands.entity(e).insert(
+                    Tween::<Translation>::to(position, LAYOUT_SECONDS).with_delay(MERGE_SECONDS),
                 );
-            });
+            }
+        } else {
             commands
-                .entity(slot_entity)
-                .insert(SelectionVisual(visual_entity.unwrap()));
+                .entity(e)
+                .remove::<InventorySlot>()
+                .remove::<Pickable>()
+                .insert(GameEntity)
+                .insert(Tween::<Scale>::to(Vec3::ZERO, LAYOUT_SECONDS).despawning());
         }
     }
+
+    let text_style = TextStyle {
+        font: font.0.clone(),
+        font_size: settings.font_size(60.0),
+        color: theme.board_text,
+    };
+    for i in missing {
+        let mut slot = commands.spawn_bundle(Text2dBundle {
+            text: Text::from_section(
+                settings.number_format.format(inventory.numbers[i]),
+                text_style.clone(),
+            )
+            .with_alignment(TextAlignment::CENTER),
+            transform: Transform::from_translation(layout.slot_position(i)).with_scale(Vec3::ZERO),
+            ..default()
+        });
+
+        slot.insert(InventorySlot { inventory_index: i })
+            .insert(Pickable {
+                min_size: Vec2::splat(80f32),
+            })
+            .insert(Tween::<Scale>::to(Vec3::ONE, LAYOUT_SECONDS).with_ease(Ease::BackOut));
+        let slot_entity = slot.id();
+        let mut visual_entity = None;
+        slot.with_children(|parent| {
+            visual_entity = Some(
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: theme.selection_hover,
+                            custom_size: Some(Vec2::splat(50f32)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(Vec2::ZERO.extend(-1f32)),
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    })
+                    .id(),
+            );
+            parent
+                .spawn_bundle(Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: settings.font_size(22.0),
+                            color: theme.board_text,
+                        },
+                    )
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_translation(Vec3::new(30f32, 30f32, 1f32)),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(OrdinalBadge);
+        });
+        commands
+            .entity(slot_entity)
+            .insert(SelectionVisual(visual_entity.unwrap()));
+    }
 }
 
-fn handle_clicks(
+/// Shows which selected number comes first, with the letter and colour of its selection.
+fn update_badges(
+    play_round: Res<PlayRound>,
+    theme: Res<Theme>,
+    mut q_badges: Query<(&Parent, &mut Text, &mut Visibility), With<OrdinalBadge>>,
+) {
+    if !play_round.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let is_number = |number: &Option<PlayingNumber>, entity: Entity| matches!(number, Some(number) if number.entity == entity);
+    for (parent, mut text, mut visibility) in &mut q_badges {
+        let badge = if is_number(&play_round.number1, parent.get()) {
+            Some(("A", theme.selection_first))
+        } else if is_number(&play_round.number2, parent.get()) {
+            Some(("B", theme.selection_second))
+        } else {
+            None
+        };
+        if let Some((letter, color)) = badge {
+            text.sections[0].value = letter.to_string();
+            text.sections[0].style.color = color;
+        }
+        visibility.is_visible = badge.is_some();
+    }
+}
+
+/// Writes out the round as `7 - 3 = ?`, with blanks for what is not selected yet.
+/// Settings apply live: the board follows the number format and text size when they change.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn restyle_board(
+    settings: Res<Settings>,
+    inventory: Res<Inventory>,
+    target: Res<TargetNumber>,
+    mut q_target: Query<&mut Text, With<TargetText>>,
+    mut q_slots: Query<(&InventorySlot, &mut Text), Without<TargetText>>,
+    mut q_operations: Query<
+        &mut Text,
+        (With<Operation>, Without<InventorySlot>, Without<TargetText>),
+    >,
+    mut q_badges: Query<
+        &mut Text,
+        (
+            With<OrdinalBadge>,
+            Without<Operation>,
+            Without<InventorySlot>,
+            Without<TargetText>,
+        ),
+    >,
+    mut q_preview: Query<
+        &mut Text,
+        (
+            With<ResultPreview>,
+            Without<OrdinalBadge>,
+            Without<Operation>,
+            Without<InventorySlot>,
+            Without<TargetText>,
+        ),
+    >,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let resize = |text: &mut Text, size: f32| {
+        for section in &mut text.sections {
+            section.style.font_size = settings.font_size(size);
+        }
+    };
+    for mut text in &mut q_target {
+        text.sections[0].value =
+            format!("Target: {}", settings.number_format.format(target.target));
+        resize(&mut text, 60.0);
+    }
+    for (slot, mut text) in &mut q_slots {
+        if let Some(number) = inventory.numbers.get(slot.inventory_index) {
+            text.sections[0].value = settings.number_format.format(*number);
+        }
+        resize(&mut text, 60.0);
+    }
+    for mut text in &mut q_operations {
+        resize(&mut text, 60.0);
+    }
+    for mut text in &mut q_badges {
+        resize(&mut text, 22.0);
+    }
+    for mut text in &mut q_preview {
+        resize(&mut text, 24.0);
+    }
+}
+
+fn update_expression(
+    play_round: Res<PlayRound>,
+    inventory: Res<Inventory>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    font: Res<TextFont>,
+    mut q_expression: Query<&mut Text, With<ExpressionText>>,
+) {
+    if !play_round.is_changed()
+        && !inventory.is_changed()
+        && !settings.is_changed()
+        && !theme.is_changed()
+    {
+        return;
+    }
+    let number = |number: &Option<PlayingNumber>| {
+        number
+            .as_ref()
+            .and_then(|number| inventory.numbers.get(number.inventory_index))
+            .map_or("_".to_string(), |value| {
+                settings.number_format.format(*value)
+            })
+    };
+    let operation = play_round
+        .operation
+        .as_ref()
+        .map_or("_".to_string(), |operation| operation.to_string());
+    let is_empty = play_round.number1.is_none()
+        && play_round.number2.is_none()
+        && play_round.operation.is_none();
+    let style = |color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: settings.font_size(30.0),
+        color,
+    };
+    let sections = if is_empty {
+        vec![]
+    } else {
+        vec![
+            TextSection::new(number(&play_round.number1), style(theme.selection_first)),
+            TextSection::new(format!(" {operation} "), style(theme.board_text)),
+            TextSection::new(number(&play_round.number2), style(theme.selection_second)),
+            TextSection::new(" = ?", style(theme.board_text)),
+        ]
+    };
+    for mut text in &mut q_expression {
+        text.sections = sections.clone();
+    }
+}
+
+/// Selects the number if there is room for it, or deselects it if it was selected.
+/// Returns false when the selection is already full.
+fn toggle_number(
+    play_round: &mut PlayRound,
+    number: PlayingNumber,
+    game_events: &mut EventWriter<GameEvent>,
+) -> bool {
+    let is_selected =
+        |selected: &Option<PlayingNumber>| matches!(selected, Some(n) if n.entity == number.entity);
+    if is_selected(&play_round.number1) {
+        play_round.number1 = None;
+        game_events.send(GameEvent::Deselected);
+    } else if is_selected(&play_round.number2) {
+        play_round.number2 = None;
+        game_events.send(GameEvent::Deselected);
+    } else if play_round.number1.is_none() {
+        play_round.number1 = Some(number);
+        game_events.send(GameEvent::NumberSelected);
+    } else if play_round.number2.is_none() {
+        play_round.number2 = Some(number);
+        game_events.send(GameEvent::NumberSelected);
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Selects the operation if none is, or deselects it if it was selected.
+/// Returns false when another operation is already selected.
+fn toggle_operation(
+    play_round: &mut PlayRound,
+    operation: &Operation,
+    game_events: &mut EventWriter<GameEvent>,
+) -> bool {
+    match &play_round.operation {
+        Some(selected) if selected == operation => {
+            play_round.operation = None;
+            game_events.send(GameEvent::Deselected);
+        }
+        Some(_) => return false,
+        None => {
+            play_round.operation = Some(operation.clone());
+            game_events.send(GameEvent::OperationSelected);
+        }
+    }
+    true
+}
+
+/// Mouse clicks and touch taps select whatever is hovered.
+#[allow(clippy::too_many_arguments)]
+fn mouse_actions(
     mut mouse_button_input_events: EventReader<MouseButtonInput>,
-    mouse_pos: Res<MousePos>,
-    mut play_round: ResMut<PlayRound>,
+    mut actions: EventWriter<GameAction>,
+    touches: Res<Touches>,
+    hover: Res<Hover>,
+    q_inventory_slots: Query<&InventorySlot>,
+    q_operations: Query<&Operation>,
+    q_preview: Query<(), With<ResultPreview>>,
+    q_expression: Query<(), With<ExpressionText>>,
+) {
+    let presses = mouse_button_input_events
+        .iter()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .count()
+        + touches.iter_just_pressed().count();
+    let hovered = match hover.0 {
+        Some(hovered) => hovered,
+        None => return,
+    };
+    for _ in 0..presses {
+        if let Ok(slot) = q_inventory_slots.get(hovered) {
+            actions.send(GameAction::SelectSlot(slot.inventory_index));
+        } else if let Ok(operation) = q_operations.get(hovered) {
+            actions.send(GameAction::SelectOperation(operation.clone()));
+        } else if q_preview.contains(hovered) {
+            actions.send(GameAction::Confirm);
+        } else if q_expression.contains(hovered) {
+            actions.send(GameAction::Swap);
+        }
+    }
+}
+
+/// Number keys pick slots in reading order, operator keys pick operations and enter confirms.
+fn keyboard_actions(
+    keys: Res<Input<KeyCode>>,
+    typing: Res<ExpressionInput>,
+    mut actions: EventWriter<GameAction>,
+) {
+    if typing.active {
+        return;
+    }
+    let slot_keys = [
+        (KeyCode::Key1, KeyCode::Numpad1),
+        (KeyCode::Key2, KeyCode::Numpad2),
+        (KeyCode::Key3, KeyCode::Numpad3),
+        (KeyCode::Key4, KeyCode::Numpad4),
+        (KeyCode::Key5, KeyCode::Numpad5),
+        (KeyCode::Key6, KeyCode::Numpad6),
+        (KeyCode::Key7, KeyCode::Numpad7),
+        (KeyCode::Key8, KeyCode::Numpad8),
+        (KeyCode::Key9, KeyCode::Numpad9),
+        (KeyCode::Key0, KeyCode::Numpad0),
+    ];
+    for (index, (key, numpad_key)) in slot_keys.into_iter().enumerate() {
+        if keys.just_pressed(key) || keys.just_pressed(numpad_key) {
+            actions.send(GameAction::SelectSlot(index));
+        }
+    }
+    let operation_keys = [
+        (Operation::Plus, KeyCode::Plus, KeyCode::NumpadAdd),
+        (Operation::Minus, KeyCode::Minus, KeyCode::NumpadSubtract),
+        (
+            Operation::Multiply,
+            KeyCode::Asterisk,
+            KeyCode::NumpadMultiply,
+        ),
+        (Operation::Divide, KeyCode::Slash, KeyCode::NumpadDivide),
+    ];
+    for (operation, key, numpad_key) in operation_keys {
+        if keys.just_pressed(key) || keys.just_pressed(numpad_key) {
+            actions.send(GameAction::SelectOperation(operation));
+        }
+    }
+    if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::NumpadEnter) {
+        actions.send(GameAction::Confirm);
+    }
+}
+
+/// Closest candidate in the given direction, favouring the ones aligned with it.
+fn neighbour(from: Vec2, direction: Vec2, candidates: &[(Entity, Vec2)]) -> Option<Entity> {
+    candidates
+        .iter()
+        .filter_map(|(entity, position)| {
+            let offset = *position - from;
+            let along = offset.dot(direction);
+            let across = (offset - direction * along).length();
+            (along > 0f32).then(|| (*entity, along + 2f32 * across))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(entity, _)| entity)
+}
+
+/// Moves the board focus, and turns the other bound inputs into [`GameAction`]s.
+fn bound_actions(
+    mut pressed: EventReader<ActionPressed>,
+    mut actions: EventWriter<GameAction>,
+    mut focus: ResMut<BoardFocus>,
+    typing: Res<ExpressionInput>,
     q_inventory_slots: Query<(Entity, &Transform, &InventorySlot)>,
     q_operations: Query<(Entity, &Transform, &Operation)>,
+    q_marker: Query<(&Transform, &Visibility), With<FocusMarker>>,
 ) {
-    for event in mouse_button_input_events.iter() {
-        if ButtonState::Pressed == event.state {
-            let mut found_something = false;
-            for (e, t, slot) in &q_inventory_slots {
-                let distance = t.translation.xy().distance(mouse_pos.0);
-                if distance < 50f32 {
-                    if let Some(n1) = &play_round.number1 {
-                        if n1.entity == e {
-                            play_round.number1 = None;
-                            found_something = true;
-                            break;
-                        }
-                    }
-                    if let Some(n2) = &play_round.number2 {
-                        if n2.entity == e {
-                            play_round.number2 = None;
-                            found_something = true;
-                            break;
-                        }
-                    }
-                    if play_round.number1.is_none() {
-                        play_round.number1 = Some(PlayingNumber {
-                            entity: e,
-                            inventory_index: slot.inventory_index,
-                        });
-                        found_something = true;
-                        break;
-                    }
-                    if play_round.number2.is_none() {
-                        play_round.number2 = Some(PlayingNumber {
-                            entity: e,
-                            inventory_index: slot.inventory_index,
-                        });
-                        found_something = true;
-                        break;
-                    }
-                    // close to something, but we cannot select more.
-                    // TODO: show feedback to encourage deselection
-                    break;
+    let candidates: Vec<(Entity, Vec2)> = q_inventory_slots
+        .iter()
+        .map(|(e, t, _)| (e, t.translation.xy()))
+        .chain(q_operations.iter().map(|(e, t, _)| (e, t.translation.xy())))
+        .collect();
+    let position = |entity: Entity| {
+        candidates
+            .iter()
+            .find(|(candidate, _)| *candidate == entity)
+            .map(|(_, position)| *position)
+    };
+    let first_slot = q_inventory_slots
+        .iter()
+        .find(|(_, _, slot)| slot.inventory_index == 0)
+        .map(|(e, ..)| e);
+    for ActionPressed(action) in pressed.iter() {
+        // Keys are text while typing an expression.
+        if typing.active {
+            continue;
+        }
+        // Slots are respawned when the inventory changes: move to the closest one.
+        if focus.0.and_then(position).is_none() {
+            focus.0 = match q_marker.get_single() {
+                Ok((marker, visibility)) if visibility.is_visible => {
+                    let from = marker.translation.xy();
+                    candidates
+                        .iter()
+                        .min_by(|(_, a), (_, b)| {
+                            a.distance(from).partial_cmp(&b.distance(from)).unwrap()
+                        })
+                        .map(|(e, _)| *e)
+                }
+                _ => None,
+            };
+        }
+        let direction = match action {
+            InputAction::Up => Some(Vec2::Y),
+            InputAction::Down => Some(-Vec2::Y),
+            InputAction::Left => Some(-Vec2::X),
+            InputAction::Right => Some(Vec2::X),
+            _ => None,
+        };
+        if focus.0.is_none() && (direction.is_some() || *action == InputAction::Select) {
+            // The first press only reveals where the focus is.
+            focus.0 = first_slot;
+            continue;
+        }
+        if let Some(direction) = direction {
+            if let Some(from) = focus.0.and_then(position) {
+                if let Some(next) = neighbour(from, direction, &candidates) {
+                    focus.0 = Some(next);
                 }
             }
-            if found_something {
-                continue;
+            continue;
+        }
+        match action {
+            InputAction::Select => {
+                if let Some(focused) = focus.0 {
+                    if let Ok((_, _, slot)) = q_inventory_slots.get(focused) {
+                        actions.send(GameAction::SelectSlot(slot.inventory_index));
+                    } else if let Ok((_, _, operation)) = q_operations.get(focused) {
+                        actions.send(GameAction::SelectOperation(operation.clone()));
+                    }
+                }
             }
-            for (e, t, operation) in &q_operations {
-                let distance = t.translation.xy().distance(mouse_pos.0);
-                if distance < 50f32 {
-                    if let Some(op) = &play_round.operation {
-                        if op == operation {
-                            play_round.operation = None;
-                            break;
+            InputAction::Deselect => actions.send(GameAction::Deselect),
+            InputAction::Confirm => actions.send(GameAction::Confirm),
+            InputAction::Swap => actions.send(GameAction::Swap),
+            InputAction::Undo => actions.send(GameAction::Undo),
+            InputAction::Hint => actions.send(GameAction::Hint),
+            InputAction::Pause => actions.send(GameAction::Pause),
+            InputAction::Restart => actions.send(GameAction::Restart),
+            InputAction::Submit => actions.send(GameAction::Submit),
+            InputAction::Up | InputAction::Down | InputAction::Left | InputAction::Right => {}
+        }
+    }
+}
+
+/// Changes the selection and inventory in response to the player, except for typed
+/// expressions whose rounds [`play_pending_steps`] selects one at a time.
+#[allow(clippy::too_many_arguments)]
+fn apply_actions(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    mut game_events: EventWriter<GameEvent>,
+    mut focus: ResMut<BoardFocus>,
+    mut play_round: ResMut<PlayRound>,
+    mut inventory: ResMut<Inventory>,
+    mut history: ResMut<History>,
+    mut state: ResMut<State<GameState>>,
+    mut submit: EventWriter<SubmitLevel>,
+    settings: Res<Settings>,
+    mut pending: ResMut<PendingSteps>,
+    q_inventory_slots: Query<(Entity, &InventorySlot)>,
+    q_operations: Query<&Operation>,
+) {
+    let slot = |index: usize| {
+        q_inventory_slots
+            .iter()
+            .find(|(_, slot)| slot.inventory_index == index)
+            .map(|(e, _)| e)
+    };
+    for action in actions.iter() {
+        match action {
+            GameAction::SelectSlot(index) => {
+                if let Some(entity) = slot(*index) {
+                    let number = PlayingNumber {
+                        entity,
+                        inventory_index: *index,
+                    };
+                    if !toggle_number(&mut play_round, number.clone(), &mut game_events) {
+                        if settings.replace_oldest_selection {
+                            // The first selected number is the oldest one.
+                            play_round.number1 = play_round.number2.take();
+                            play_round.number2 = Some(number);
+                            game_events.send(GameEvent::NumberSelected);
+                        } else {
+                            game_events.send(GameEvent::SelectionFull);
                         }
                     }
-                    if play_round.operation.is_none() {
+                }
+            }
+            GameAction::SelectOperation(operation) => {
+                if q_operations.iter().any(|available| available == operation)
+                    && !toggle_operation(&mut play_round, operation, &mut game_events)
+                {
+                    if settings.replace_oldest_selection {
                         play_round.operation = Some(operation.clone());
-                        break;
+                        game_events.send(GameEvent::OperationSelected);
+                    } else {
+                        game_events.send(GameEvent::SelectionFull);
                     }
-                    // close to something, but we cannot select more.
-                    // TODO: show feedback to encourage deselection
-                    break;
                 }
             }
+            GameAction::Deselect => {
+                if play_round.operation.is_some()
+                    || play_round.number1.is_some()
+                    || play_round.number2.is_some()
+                {
+                    play_round.reset();
+                    game_events.send(GameEvent::Deselected);
+                }
+            }
+            // Only careful mode waits for it, other rounds are played as soon as they are complete.
+            GameAction::Confirm => {
+                if play_round.is_complete() {
+                    play_round.confirmed = true;
+                }
+            }
+            GameAction::Swap => {
+                if play_round.number1.is_some() && play_round.number2.is_some() {
+                    let play_round = &mut *play_round;
+                    std::mem::swap(&mut play_round.number1, &mut play_round.number2);
+                    game_events.send(GameEvent::Swapped);
+                }
+            }
+            GameAction::Undo => {
+                if let Some(previous) = history.0.pop() {
+                    *inventory = previous;
+                    play_round.reset();
+                    pending.0.clear();
+                    game_events.send(GameEvent::Undone);
+                }
+            }
+            // Searched for over a few frames by [`search_hint`].
+            GameAction::Hint => {}
+            GameAction::Pause => {
+                let _ = state.push(GameState::Paused);
+            }
+            // Confirmed by a second press in [`confirm_restart`].
+            GameAction::Restart => {}
+            GameAction::Submit => submit.send(SubmitLevel),
+        }
+    }
+}
+
+fn handle_buttons(mut pressed: EventReader<ButtonPressed>, mut actions: EventWriter<GameAction>) {
+    for ButtonPressed(action) in pressed.iter() {
+        match action {
+            ButtonAction::Pause => actions.send(GameAction::Pause),
+            ButtonAction::Submit => actions.send(GameAction::Submit),
+            _ => {}
+        }
+    }
+}
+
+/// Restarts on a second press of restart, so that a stray press doesn't wipe the progress.
+fn confirm_restart(
+    mut actions: EventReader<GameAction>,
+    time: Res<Time>,
+    mut requested_at: Local<Option<f64>>,
+    mut restart: EventWriter<RestartLevel>,
+    mut game_events: EventWriter<GameEvent>,
+) {
+    let now = time.seconds_since_startup();
+    for action in actions.iter() {
+        if *action != GameAction::Restart {
+            continue;
+        }
+        match *requested_at {
+            Some(at) if now - at <= RESTART_CONFIRM_SECONDS => {
+                *requested_at = None;
+                restart.send(RestartLevel);
+            }
+            _ => {
+                *requested_at = Some(now);
+                game_events.send(GameEvent::RestartRequested);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn restart_level(
+    mut commands: Commands,
+    mut restart: EventReader<RestartLevel>,
+    level: Res<Level>,
+    mut inventory: ResMut<Inventory>,
+    mut play_round: ResMut<PlayRound>,
+    mut history: ResMut<History>,
+    mut level_time: ResMut<LevelTime>,
+    mut pending: ResMut<PendingSteps>,
+    mut input: ResMut<ExpressionInput>,
+    mut focus: ResMut<BoardFocus>,
+    mut hint: ResMut<HintSearch>,
+    q_tooltips: Query<Entity, With<Tooltip>>,
+) {
+    if restart.iter().count() > 0 {
+        *inventory = Inventory::dealt(generate_puzzle(&level).numbers);
+        play_round.reset();
+        pending.0.clear();
+        history.0.clear();
+        level_time.0.reset();
+        *input = ExpressionInput::default();
+        focus.0 = None;
+        hint.0 = None;
+        for tooltip in &q_tooltips {
+            commands.entity(tooltip).despawn_recursive();
+        }
+    }
+}
+
+/// Opens the expression box with `E` or a click, then edits it until enter or escape.
+#[allow(clippy::too_many_arguments)]
+fn type_expression(
+    keys: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut input: ResMut<ExpressionInput>,
+    mut pending: ResMut<PendingSteps>,
+    inventory: Res<Inventory>,
+    rules: Res<Rules>,
+    q_operations: Query<&Operation>,
+    q_box: Query<&Interaction, (Changed<Interaction>, With<ExpressionBox>)>,
+) {
+    if !input.active {
+        // Drops the character of the key that opens the box.
+        characters.iter().count();
+        let clicked = q_box
+            .iter()
+            .any(|interaction| *interaction == Interaction::Clicked);
+        if (clicked || keys.just_pressed(KeyCode::E)) && pending.0.is_empty() {
+            input.active = true;
+        }
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        input.active = false;
+        return;
+    }
+    for ReceivedCharacter { char: c, .. } in characters.iter() {
+        if c.is_ascii_digit() || " .+-*/x×÷()".contains(*c) {
+            input.text.push(*c);
+            input.error = None;
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        input.text.pop();
+        input.error = None;
+    }
+    if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::NumpadEnter) {
+        let operations: Vec<Operation> = q_operations.iter().cloned().collect();
+        match expression::plan(
+            &input.text,
+            &inventory.numbers,
+            &operations,
+            rules.arithmetic,
+        ) {
+            Ok(steps) => {
+                pending.0 = steps.into();
+                *input = ExpressionInput::default();
+            }
+            Err(error) => input.error = Some(error),
+        }
+    }
+}
+
+/// Shows the typed text with a caret, and highlights where it went wrong.
+fn show_expression_input(
+    input: Res<ExpressionInput>,
+    theme: Res<Theme>,
+    mut q_box: Query<&mut UiColor, With<ExpressionBox>>,
+    mut q_text: Query<&mut Text, With<ExpressionInputText>>,
+    mut q_error: Query<&mut Text, (With<ExpressionErrorText>, Without<ExpressionInputText>)>,
+) {
+    if !input.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for mut color in &mut q_box {
+        color.0 = if input.active {
+            theme.button_hovered
+        } else {
+            theme.button
+        };
+    }
+    for mut text in &mut q_text {
+        let style = text.sections[0].style.clone();
+        let section = |value: String, color: Color| TextSection {
+            value,
+            style: TextStyle {
+                color,
+                ..style.clone()
+            },
+        };
+        text.sections = if !input.active && input.text.is_empty() {
+            vec![section(
+                "Press E to type a calculation".to_string(),
+                theme.text,
+            )]
+        } else {
+            let chars: Vec<char> = input.text.chars().collect();
+            let split = input
+                .error
+                .as_ref()
+                .map_or(chars.len(), |error| error.position.min(chars.len()));
+            let offending = match chars.get(split) {
+                Some(c) => c.to_string(),
+                None if input.error.is_some() => "_".to_string(),
+                None => String::new(),
+            };
+            let after: String = chars.iter().skip(split + 1).collect();
+            let caret = if input.active { "|" } else { "" };
+            vec![
+                section(chars[..split].iter().collect(), theme.text),
+                section(offending, theme.background_failed),
+                section(after, theme.text),
+                section(caret.to_string(), theme.text),
+            ]
+        };
+    }
+    for mut text in &mut q_error {
+        text.sections[0].value = input
+            .error
+            .as_ref()
+            .map_or(String::new(), |error| error.to_string());
+        text.sections[0].style.color = theme.background_failed;
+    }
+}
+
+/// Plays the rounds of a submitted expression one per frame, through [`react_play_round`]
+/// like the player's own selections.
+fn play_pending_steps(
+    mut pending: ResMut<PendingSteps>,
+    mut play_round: ResMut<PlayRound>,
+    q_inventory_slots: Query<(Entity, &InventorySlot)>,
+) {
+    let step = match pending.0.pop_front() {
+        Some(step) => step,
+        None => return,
+    };
+    let slot = |index: usize| {
+        q_inventory_slots
+            .iter()
+            .find(|(_, slot)| slot.inventory_index == index)
+            .map(|(entity, _)| PlayingNumber {
+                entity,
+                inventory_index: index,
+            })
+    };
+    if let (Some(first), Some(second)) = (slot(step.first), slot(step.second)) {
+        play_round.reset();
+        play_round.number1 = Some(first);
+        play_round.number2 = Some(second);
+        play_round.operation = Some(step.operation);
+        play_round.confirmed = true;
+    } else {
+        pending.0.clear();
+    }
+}
+
+/// Searches for the step asked for with [`GameAction::Hint`] without stalling a frame,
+/// then selects its first number and operation and wobbles the number to pick next.
+#[allow(clippy::too_many_arguments)]
+fn search_hint(
+    mut commands: Commands,
+    mut actions: EventReader<GameAction>,
+    mut game_events: EventWriter<GameEvent>,
+    mut search: ResMut<HintSearch>,
+    mut focus: ResMut<BoardFocus>,
+    mut play_round: ResMut<PlayRound>,
+    inventory: Res<Inventory>,
+    rules: Res<Rules>,
+    target: Res<TargetNumber>,
+    q_inventory_slots: Query<(Entity, &InventorySlot)>,
+    q_operations: Query<&Operation>,
+) {
+    if actions.iter().any(|action| *action == GameAction::Hint) {
+        let operations: Vec<Operation> = q_operations.iter().cloned().collect();
+        search.0 = Some(solver::Search::new(
+            &inventory.numbers,
+            &operations,
+            rules.arithmetic,
+            target.target,
+        ));
+    }
+    let running = match search.0.as_mut() {
+        Some(running) => running,
+        None => return,
+    };
+    // A round played meanwhile makes the hint stale.
+    if running.numbers() != inventory.numbers.as_slice() {
+        search.0 = None;
+        return;
+    }
+    if !running.run(HINT_POSITIONS_PER_FRAME) {
+        return;
+    }
+    let step = running.best();
+    search.0 = None;
+    let slot = |index: usize| {
+        q_inventory_slots
+            .iter()
+            .find(|(_, slot)| slot.inventory_index == index)
+            .map(|(e, _)| e)
+    };
+    if let Some(step) = step {
+        if let (Some(first), Some(second)) = (slot(step.first), slot(step.second)) {
+            // Leave the last number for the player to pick.
+            play_round.reset();
+            play_round.number1 = Some(PlayingNumber {
+                entity: first,
+                inventory_index: step.first,
+            });
+            play_round.operation = Some(step.operation);
+            focus.0 = Some(second);
+            game_events.send(GameEvent::NumberSelected);
+            // Wobble the number to pick next.
+            let tilt = |angle: f32| Quat::from_rotation_z(angle);
+            commands.entity(second).insert(
+                Tween::<Rotation>::to(tilt(0.2f32), 0.1f32)
+                    .then(tilt(-0.2f32), 0.2f32)
+                    .then(tilt(0.1f32), 0.15f32)
+                    .then(Quat::IDENTITY, 0.1f32),
+            );
+        }
+    }
+}
+
+/// Ends the level once a single number is left, or earlier when the rules allow it.
+///
+/// Dealt numbers never end it early: only numbers made by a round can reach the target
+/// or be submitted.
+#[allow(clippy::too_many_arguments)]
+fn end_level(
+    mut submit: EventReader<SubmitLevel>,
+    mut game_events: EventWriter<GameEvent>,
+    mut level_over: ResMut<LevelOver>,
+    // TODO: shoud be in the done state
+    mut game_result: ResMut<GameResult>,
+    mut pending: ResMut<PendingSteps>,
+    inventory: Res<Inventory>,
+    target: Res<TargetNumber>,
+    level_time: Res<LevelTime>,
+    rules: Res<Rules>,
+) {
+    let submitted = submit.iter().count() > 0 && rules.can_submit;
+    if level_over.0 || (!submitted && !inventory.is_changed()) {
+        return;
+    }
+    let distance = |number: &f32| (number - target.target).abs();
+    let closest_made = inventory
+        .numbers
+        .iter()
+        .zip(&inventory.made)
+        .filter(|(_, made)| **made)
+        .map(|(number, _)| *number)
+        .min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap());
+    let last_number = match (inventory.numbers.as_slice(), closest_made) {
+        ([last], _) => *last,
+        (_, Some(closest)) if submitted => closest,
+        (_, Some(closest)) if rules.finish_on_target && closest == target.target => closest,
+        (_, None) if submitted => {
+            game_events.send(GameEvent::NothingToSubmit);
+            return;
+        }
+        _ => return,
+    };
+    game_result.target_number = target.target;
+    game_result.last_number = last_number;
+    game_result.seconds = level_time.0.elapsed_secs();
+    game_result.unused_numbers = inventory.numbers.len() - 1;
+    game_result.unused_number_points = rules.unused_number_points;
+    level_over.0 = true;
+    pending.0.clear();
+}
+
+/// Shows the done screen once the last merge has burst.
+fn leave_finished_level(
+    level_over: Res<LevelOver>,
+    game_result: Res<GameResult>,
+    mut game_events: EventWriter<GameEvent>,
+    mut state: ResMut<State<GameState>>,
+    q_merging: Query<(), With<MergingInto>>,
+) {
+    if level_over.0 && q_merging.is_empty() && state.set(GameState::Done).is_ok() {
+        game_events.send(GameEvent::LevelFinished(game_result.outcome()));
+    }
+}
+
+/// The board doesn't take any more actions once its result is decided.
+fn hold_actions_when_over(level_over: Res<LevelOver>, mut actions: ResMut<Events<GameAction>>) {
+    if level_over.0 {
+        actions.clear();
+    }
+}
+
+fn tick_level_time(clock: Res<GameClock>, mut level_time: ResMut<LevelTime>) {
+    level_time.0.tick(clock.delta());
+}
+
+/// The buttons and the board under the pause menu don't react while it is open.
+fn disable_board(
+    mut commands: Commands,
+    q_disabled: Query<Entity, Or<(With<PauseButton>, With<SubmitButton>, With<Pickable>)>>,
+) {
+    for entity in &q_disabled {
+        commands.entity(entity).insert(Disabled);
+    }
+}
+
+fn enable_board(
+    mut commands: Commands,
+    q_disabled: Query<Entity, Or<(With<PauseButton>, With<SubmitButton>, With<Pickable>)>>,
+) {
+    for entity in &q_disabled {
+        commands.entity(entity).remove::<Disabled>();
+    }
+}
+
+/// Presses from the pause menu are still pending when coming back to the level, drop them.
+fn clear_stale_input(
+    mut actions: ResMut<Events<GameAction>>,
+    mut mouse_button_input_events: ResMut<Events<MouseButtonInput>>,
+) {
+    actions.clear();
+    mouse_button_input_events.clear();
+}
+
+fn update_focus_marker(
+    focus: Res<BoardFocus>,
+    theme: Res<Theme>,
+    q_focusable: Query<&Transform, Without<FocusMarker>>,
+    mut q_marker: Query<(&mut Transform, &mut Visibility, &mut Sprite), With<FocusMarker>>,
+) {
+    for (mut transform, mut visibility, mut sprite) in &mut q_marker {
+        // Otherwise keep the last position, to find the closest slot once they are respawned.
+        if let Some(focused) = focus.0.and_then(|focused| q_focusable.get(focused).ok()) {
+            transform.translation.x = focused.translation.x;
+            transform.translation.y = focused.translation.y;
+            visibility.is_visible = true;
+        }
+        let mut color = theme.board_text;
+        color.set_a(0.25);
+        if sprite.color != color {
+            sprite.color = color;
         }
     }
 }
 
+/// Colours the selection behind slots and operations, and their text, which isn't [`Themed`]
+/// so that a theme change can't paint over a selection.
 fn visibility_selection(
+    mut commands: Commands,
     mut play_round: ResMut<PlayRound>,
+    theme: Res<Theme>,
+    hover: Res<Hover>,
     q_selectable: Query<(Entity, &SelectionVisual, Option<&Operation>)>,
     mut q_visibility: Query<(&mut Visibility, &mut Sprite)>,
+    mut q_texts: Query<&mut Text>,
 ) {
-    if play_round.is_changed() {
+    if play_round.is_changed() || theme.is_changed() || hover.is_changed() {
         for (e, v, op) in q_selectable.iter() {
-            if let Some(n1) = &play_round.number1 {
-                if n1.entity == e {
-                    let mut res = q_visibility.get_mut(v.0).unwrap();
-                    res.0.is_visible = true;
-                    res.1.color = Color::GREEN;
-                    continue;
-                }
-            }
-            if let Some(n2) = &play_round.number2 {
-                if n2.entity == e {
-                    let mut res = q_visibility.get_mut(v.0).unwrap();
-                    res.0.is_visible = true;
-                    res.1.color = Color::BLUE;
-                    continue;
+            let is_number = |number: &Option<PlayingNumber>| matches!(number, Some(number) if number.entity == e);
+            let selected = if is_number(&play_round.number1) {
+                Some(theme.selection_first)
+            } else if is_number(&play_round.number2) {
+                Some(theme.selection_second)
+            } else if op.is_some() && op == play_round.operation.as_ref() {
+                Some(theme.selection_operation)
+            } else {
+                None
+            };
+            let color = if selected.is_none() && hover.0 == Some(e) {
+                Some(theme.selection_hover)
+            } else {
+                selected
+            };
+            let text_color = if selected.is_some() {
+                theme.selection_text
+            } else {
+                theme.board_text
+            };
+            if let Ok(mut text) = q_texts.get_mut(e) {
+                for section in &mut text.sections {
+                    if section.style.color != text_color {
+                        section.style.color = text_color;
+                    }
                 }
             }
-            if let Some(op) = op {
-                if let Some(selected_op) = &play_round.operation {
-                    if selected_op == op {
-                        let mut res = q_visibility.get_mut(v.0).unwrap();
-                        res.0.is_visible = true;
-                        res.1.color = Color::YELLOW_GREEN;
-                        continue;
-                    }
+            let (mut visibility, mut sprite) = q_visibility.get_mut(v.0).unwrap();
+            if let Some(color) = color {
+                if !visibility.is_visible {
+                    sprite.color = color;
+                    commands.entity(v.0).insert(
+                        Tween::<Scale>::from_to(Vec3::splat(0.6f32), Vec3::ONE, 0.15f32)
+                            .with_ease(Ease::BackOut),
+                    );
+                } else if sprite.color != color {
+                    commands
+                        .entity(v.0)
+                        .insert(Tween::<SpriteColor>::to(color, 0.15f32));
                 }
+                visibility.is_visible = true;
+            } else {
+                visibility.is_visible = false;
             }
-            q_visibility.get_mut(v.0).unwrap().0.is_visible = false;
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn react_play_round(
-    mut particles: EventWriter<ParticleExplosion>,
+    mut commands: Commands,
+    mut game_events: EventWriter<GameEvent>,
     mut inventory: ResMut<Inventory>,
     mut play_round: ResMut<PlayRound>,
-    mut state: ResMut<State<GameState>>,
-    // TODO: shoud be in the done state
-    mut game_result: ResMut<GameResult>,
-    target: Res<TargetNumber>,
+    mut history: ResMut<History>,
+    mut q_inventory_slots: Query<(&mut InventorySlot, &Transform)>,
+    q_operations: Query<(&Transform, &Operation), Without<InventorySlot>>,
+    settings: Res<Settings>,
+    rules: Res<Rules>,
 ) {
-    if play_round.is_changed() {
+    if play_round.is_changed() && (play_round.confirmed || !settings.careful_mode) {
         if let PlayRound {
             operation: Some(op),
             number1: Some(n1),
             number2: Some(n2),
+            ..
         } = play_round.clone()
         {
             let result = op.apply(
                 inventory.numbers[n1.inventory_index],
                 inventory.numbers[n2.inventory_index],
+                rules.arithmetic,
             );
             if let Ok(result) = result {
+                history.0.push(inventory.clone());
                 play_round.as_mut().reset();
                 inventory.numbers[n1.inventory_index] = result;
                 inventory.numbers.remove(n2.inventory_index);
-                particles.send(ParticleExplosion {
-                    location: Vec2::ZERO,
-                    color: Color::ANTIQUE_WHITE,
-                });
-                if inventory.numbers.len() == 1 {
-                    game_result.target_number = target.target;
-                    game_result.last_number = inventory.numbers[0];
+                inventory.made[n1.inventory_index] = true;
+                inventory.made.remove(n2.inventory_index);
+                game_events.send(GameEvent::Combined);
 
-                    let _ = state.set(GameState::Done);
+                // The second number slides onto the first one, which pops with the result.
+                if let Ok((_, first)) = q_inventory_slots.get(n1.entity) {
+                    commands
+                        .entity(n2.entity)
+                        .remove::<InventorySlot>()
+                        .remove::<Pickable>()
+                        .insert(GameEntity)
+                        .insert(MergingInto(n1.entity))
+                        .insert(
+                            Tween::<Translation>::to(first.translation, MERGE_SECONDS)
+                                .with_ease(Ease::QuadIn),
+                        )
+                        .insert(Tween::<Scale>::to(Vec3::splat(0.5f32), MERGE_SECONDS));
+                    commands.entity(n1.entity).insert(
+                        Tween::<Scale>::from_to(Vec3::splat(1.4f32), Vec3::ONE, POP_SECONDS)
+                            .with_delay(MERGE_SECONDS)
+                            .with_ease(Ease::QuadOut),
+                    );
                 }
-            } else {
+                for (mut slot, _) in &mut q_inventory_slots {
+                    if slot.inventory_index > n2.inventory_index {
+                        slot.inventory_index -= 1;
+                    }
+                }
+                if inventory.numbers.len() > 1 {
+                    if settings.chain_mode.keeps_result() {
+                        let inventory_index = if n1.inventory_index > n2.inventory_index {
+                            n1.inventory_index - 1
+                        } else {
+                            n1.inventory_index
+                        };
+                        play_round.number1 = Some(PlayingNumber {
+                            entity: n1.entity,
+                            inventory_index,
+                        });
+                    }
+                    if settings.chain_mode.keeps_operation() {
+                        play_round.operation = Some(op);
+                    }
+                }
+            } else if let Err(reason) = result {
                 play_round.as_mut().reset();
-                particles.send(ParticleExplosion {
-                    location: Vec2::ZERO,
-                    color: Color::ORANGE_RED,
-                })
+                let location = q_operations
+                    .iter()
+                    .find(|(_, o)| **o == op)
+                    .map(|(transform, _)| transform.translation.truncate())
+                    .unwrap_or_default();
+                game_events.send(GameEvent::InvalidOperation { reason, location });
             }
         }
     }
 }
+
+#[allow(clippy::too_many_arguments)]
+fn selection_feedback(
+    mut commands: Commands,
+    mut game_events: EventReader<GameEvent>,
+    font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    layout: Res<BoardLayout>,
+    play_round: Res<PlayRound>,
+    q_selectable: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&Operation>,
+            Option<&Tween<Translation>>,
+        ),
+        With<SelectionVisual>,
+    >,
+    q_tooltips: Query<Entity, With<Tooltip>>,
+) {
+    for event in game_events.iter() {
+        let message = match event {
+            GameEvent::SelectionFull => "Selection is full, deselect something first".to_string(),
+            GameEvent::NothingToSubmit => "Combine some numbers before submitting".to_string(),
+            GameEvent::RestartRequested => "Press restart again to start over".to_string(),
+            GameEvent::InvalidOperation { reason, .. } => reason.to_string(),
+            _ => continue,
+        };
+        for tooltip in &q_tooltips {
+            commands.entity(tooltip).despawn_recursive();
+        }
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::from_section(
+                    message,
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: settings.font_size(30.0),
+                        color: theme.board_text,
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform: Transform::from_translation(layout.tooltip.extend(30f32)),
+                ..default()
+            })
+            .insert(Tooltip)
+            .insert(Lifetime::despawning(TOOLTIP_SECONDS + TOOLTIP_FADE_SECONDS))
+            .insert(
+                Tween::<TextAlpha>::to(0f32, TOOLTIP_FADE_SECONDS)
+                    .with_delay(TOOLTIP_SECONDS)
+                    .with_ease(Ease::Linear),
+            )
+            .insert(Themed(ThemeColor::BoardText))
+            .insert(GameEntity);
+
+        if *event != GameEvent::SelectionFull {
+            continue;
+        }
+        for (e, transform, operation, moving) in &q_selectable {
+            let is_selected = [&play_round.number1, &play_round.number2]
+                .into_iter()
+                .flatten()
+                .any(|number| number.entity == e)
+                || matches!((operation, &play_round.operation), (Some(op), Some(selected)) if op == selected);
+            if is_selected {
+                // Wiggle sideways around where the entity is headed, to refuse the input.
+                let origin = moving.map_or(transform.translation, |tween| tween.end_value());
+                let offset = |x: f32| origin + Vec3::X * x;
+                commands.entity(e).insert(
+                    Tween::<Translation>::to(offset(8f32), SHAKE_STEP_SECONDS)
+                        .then(offset(-8f32), 2f32 * SHAKE_STEP_SECONDS)
+                        .then(offset(4f32), 1.5f32 * SHAKE_STEP_SECONDS)
+                        .then(origin, SHAKE_STEP_SECONDS),
+                );
+            }
+        }
+    }
+}
+
+/// Bursts on the result slot once the merging number has reached it.
+///
+/// Runs in every state, so that a merge finishing as the pause menu opens isn't missed.
+fn finish_merges(
+    mut commands: Commands,
+    mut finished: EventReader<TweenFinished>,
+    mut particles: EventWriter<ParticleExplosion>,
+    q_merging: Query<&MergingInto>,
+    q_transforms: Query<&Transform>,
+) {
+    for TweenFinished { entity, lens } in finished.iter() {
+        let result = match q_merging.get(*entity) {
+            Ok(MergingInto(result)) if *lens == TweenLens::Scale => result,
+            _ => continue,
+        };
+        commands.entity(*entity).despawn_recursive();
+        if let Ok(transform) = q_transforms.get(*result) {
+            particles.send(ParticleExplosion {
+                location: transform.translation.truncate(),
+                effect: ParticleEffect::Success,
+            });
+        }
+    }
+}
+
+fn particles_on_game_events(
+    mut game_events: EventReader<GameEvent>,
+    mut particles: EventWriter<ParticleExplosion>,
+) {
+    for event in game_events.iter() {
+        let (location, effects): (Vec2, &[ParticleEffect]) = match event {
+            GameEvent::InvalidOperation { location, .. } => (*location, &[ParticleEffect::Error]),
+            GameEvent::LevelFinished(LevelOutcome::Won) => {
+                (Vec2::ZERO, &[ParticleEffect::Win, ParticleEffect::Confetti])
+            }
+            GameEvent::LevelFinished(LevelOutcome::Close) => (Vec2::ZERO, &[ParticleEffect::Win]),
+            _ => continue,
+        };
+        for effect in effects {
+            particles.send(ParticleExplosion {
+                location,
+                effect: *effect,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+
+    /// Puzzles must never change for a given level, or shared seeds stop meaning anything.
+    #[test]
+    fn puzzles_match_golden_file() {
+        let mut generated = String::new();
+        for seed in [0, 42, 0xdead_beef, u64::MAX] {
+            for level_index in [0, 1, 5, 12, 30] {
+                for arithmetic in Arithmetic::ALL {
+                    let level = Level {
+                        seed,
+                        level_index,
+                        arithmetic,
+                    };
+                    let puzzle = generate_puzzle(&level);
+                    let operations: Vec<String> =
+                        puzzle.operations.iter().map(|o| o.to_string()).collect();
+                    writeln!(
+                        generated,
+                        "{seed} {level_index} {arithmetic:?}: {:?} {} = {}",
+                        puzzle.numbers,
+                        operations.join(" "),
+                        puzzle.target
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        assert_eq!(generated, include_str!("../tests/puzzles.golden"));
+    }
+
+    #[test]
+    fn unused_numbers_only_reward_a_result() {
+        let result = |last_number| GameResult {
+            last_number,
+            target_number: 10f32,
+            seconds: 0f32,
+            unused_numbers: 3,
+            unused_number_points: 10,
+        };
+        assert_eq!(result(10f32).score(), 130);
+        assert_eq!(result(10.5f32).score(), 80);
+        assert_eq!(result(2f32).score(), 0);
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{storage, ui::UiNavigation};
+
+const INPUT_KEY: &str = "input";
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        let input_map = storage::load::<InputMap>(INPUT_KEY)
+            .map(InputMap::with_new_defaults)
+            .unwrap_or_default();
+        // Written out so players can find and edit their bindings, new ones included.
+        storage::save(INPUT_KEY, &input_map);
+        app.insert_resource(input_map)
+            .add_event::<ActionPressed>()
+            .add_system(keyboard_actions)
+            .add_system(gamepad_actions)
+            .add_system(ui_navigation.after(keyboard_actions).after(gamepad_actions));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Deselect,
+    Confirm,
+    /// Exchanges the first and second selected numbers.
+    Swap,
+    Undo,
+    Hint,
+    Pause,
+    Restart,
+    /// Ends the level with the closest number, in modes allowing it.
+    Submit,
+}
+
+/// Sent when a bound key or gamepad button is pressed, or a stick is pushed past the dead zone.
+pub struct ActionPressed(pub InputAction);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    /// Arrows, tab and enter always navigate menus, in addition to these.
+    pub keyboard: Vec<(KeyCode, InputAction)>,
+    pub gamepad: Vec<(GamepadButtonType, InputAction)>,
+    pub stick_dead_zone: f32,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            keyboard: vec![
+                (KeyCode::Back, InputAction::Deselect),
+                (KeyCode::S, InputAction::Swap),
+                (KeyCode::Z, InputAction::Undo),
+                (KeyCode::H, InputAction::Hint),
+                (KeyCode::Escape, InputAction::Pause),
+                (KeyCode::R, InputAction::Restart),
+                (KeyCode::Space, InputAction::Submit),
+            ],
+            gamepad: vec![
+                (GamepadButtonType::DPadUp, InputAction::Up),
+                (GamepadButtonType::DPadDown, InputAction::Down),
+                (GamepadButtonType::DPadLeft, InputAction::Left),
+                (GamepadButtonType::DPadRight, InputAction::Right),
+                (GamepadButtonType::South, InputAction::Select),
+                (GamepadButtonType::East, InputAction::Deselect),
+                (GamepadButtonType::West, InputAction::Confirm),
+                (GamepadButtonType::North, InputAction::Swap),
+                (GamepadButtonType::LeftTrigger, InputAction::Undo),
+                (GamepadButtonType::RightTrigger, InputAction::Hint),
+                (GamepadButtonType::Start, InputAction::Pause),
+                (GamepadButtonType::Select, InputAction::Restart),
+                (GamepadButtonType::RightTrigger2, InputAction::Submit),
+            ],
+            stick_dead_zone: 0.5,
+        }
+    }
+}
+
+impl InputMap {
+    /// Adds the default bindings of actions that came after the map was saved.
+    ///
+    /// Actions already bound are left alone, as are defaults whose key is taken.
+    fn with_new_defaults(mut self) -> Self {
+        let defaults = Self::default();
+        add_missing(&mut self.keyboard, defaults.keyboard);
+        add_missing(&mut self.gamepad, defaults.gamepad);
+        self
+    }
+}
+
+fn add_missing<T: PartialEq>(
+    bindings: &mut Vec<(T, InputAction)>,
+    defaults: Vec<(T, InputAction)>,
+) {
+    for (input, action) in defaults {
+        let unbound = bindings.iter().all(|(_, bound)| *bound != action);
+        let free = bindings.iter().all(|(taken, _)| *taken != input);
+        if unbound && free {
+            bindings.push((input, action));
+        }
+    }
+}
+
+/// Drops pending actions, so that a screen showing up doesn't react to the press that opened it.
+pub fn clear_actions(mut pressed: ResMut<Events<ActionPressed>>) {
+    pressed.clear();
+}
+
+fn keyboard_actions(
+    input_map: Res<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mut actions: EventWriter<ActionPressed>,
+) {
+    for (key, action) in &input_map.keyboard {
+        if keys.just_pressed(*key) {
+            actions.send(ActionPressed(*action));
+        }
+    }
+}
+
+fn gamepad_actions(
+    input_map: Res<InputMap>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut stick_pushed: Local<Vec<(Gamepad, InputAction)>>,
+    mut actions: EventWriter<ActionPressed>,
+) {
+    for gamepad in gamepads.iter() {
+        for (button, action) in &input_map.gamepad {
+            if buttons.just_pressed(GamepadButton::new(*gamepad, *button)) {
+                actions.send(ActionPressed(*action));
+            }
+        }
+
+        let stick = Vec2::new(
+            axes.get(GamepadAxis::new(*gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or_default(),
+            axes.get(GamepadAxis::new(*gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or_default(),
+        );
+        let direction = if stick.length() < input_map.stick_dead_zone {
+            None
+        } else if stick.x.abs() > stick.y.abs() {
+            Some(if stick.x > 0f32 {
+                InputAction::Right
+            } else {
+                InputAction::Left
+            })
+        } else {
+            Some(if stick.y > 0f32 {
+                InputAction::Up
+            } else {
+                InputAction::Down
+            })
+        };
+        // Only send once per push, until the stick goes back to rest or changes direction.
+        let previous = stick_pushed
+            .iter()
+            .position(|(pushed_gamepad, _)| pushed_gamepad == gamepad);
+        match (previous, direction) {
+            (Some(index), Some(direction)) if stick_pushed[index].1 != direction => {
+                stick_pushed[index].1 = direction;
+                actions.send(ActionPressed(direction));
+            }
+            (Some(_), Some(_)) => {}
+            (Some(index), None) => {
+                stick_pushed.remove(index);
+            }
+            (None, Some(direction)) => {
+                stick_pushed.push((*gamepad, direction));
+                actions.send(ActionPressed(direction));
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn ui_navigation(
+    mut actions: EventReader<ActionPressed>,
+    mut navigation: EventWriter<UiNavigation>,
+) {
+    for ActionPressed(action) in actions.iter() {
+        match action {
+            InputAction::Up | InputAction::Left => navigation.send(UiNavigation::Previous),
+            InputAction::Down | InputAction::Right => navigation.send(UiNavigation::Next),
+            InputAction::Select | InputAction::Confirm => navigation.send(UiNavigation::Activate),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_defaults_join_a_saved_map() {
+        let saved = InputMap {
+            keyboard: vec![(KeyCode::S, InputAction::Undo)],
+            gamepad: vec![],
+            stick_dead_zone: 0.5,
+        };
+        let merged = saved.with_new_defaults();
+        // S stays on undo, and undo doesn't get Z on top.
+        assert!(merged.keyboard.contains(&(KeyCode::S, InputAction::Undo)));
+        assert!(!merged
+            .keyboard
+            .iter()
+            .any(|binding| binding.1 == InputAction::Swap));
+        assert!(!merged.keyboard.contains(&(KeyCode::Z, InputAction::Undo)));
+        assert!(merged.keyboard.contains(&(KeyCode::H, InputAction::Hint)));
+        assert!(merged
+            .gamepad
+            .contains(&(GamepadButtonType::North, InputAction::Swap)));
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::{
+    prelude::*,
+    window::{WindowId, WindowResized},
+};
+
+use crate::cursor::MainCamera;
+
+/// World area the landscape board is designed for; the default window shows it unscaled.
+const LANDSCAPE_AREA: Vec2 = Vec2::new(640f32, 720f32);
+/// Narrower and taller, with fewer columns so the inventory wraps onto more rows.
+const PORTRAIT_AREA: Vec2 = Vec2::new(480f32, 860f32);
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoardLayout>().add_system(fit_to_window);
+    }
+}
+
+/// Where the board goes, in world units, for the current shape of the window.
+///
+/// The camera zooms so that the whole design area of the layout is visible.
+pub struct BoardLayout {
+    pub scale: f32,
+    pub target: Vec2,
+    /// The round being put together, between the target and the inventory.
+    pub expression: Vec2,
+    /// Centre of the first inventory row, low enough under the expression for the badges
+    /// of the selected slots.
+    pub inventory: Vec2,
+    pub columns: usize,
+    pub spacing: f32,
+    /// Centre of the operations row.
+    pub operations: Vec2,
+    pub tooltip: Vec2,
+}
+
+impl Default for BoardLayout {
+    fn default() -> Self {
+        Self {
+            scale: 1f32,
+            target: Vec2::new(0f32, 300f32),
+            expression: Vec2::new(0f32, 250f32),
+            inventory: Vec2::new(0f32, 180f32),
+            columns: 5,
+            spacing: 100f32,
+            operations: Vec2::new(0f32, -50f32),
+            tooltip: Vec2::new(0f32, -150f32),
+        }
+    }
+}
+
+impl BoardLayout {
+    fn for_window(width: f32, height: f32) -> Self {
+        let (area, layout) = if height > width {
+            (
+                PORTRAIT_AREA,
+                Self {
+                    target: Vec2::new(0f32, 350f32),
+                    expression: Vec2::new(0f32, 290f32),
+                    inventory: Vec2::new(0f32, 210f32),
+                    columns: 4,
+                    operations: Vec2::new(0f32, -130f32),
+                    tooltip: Vec2::new(0f32, -240f32),
+                    ..default()
+                },
+            )
+        } else {
+            (LANDSCAPE_AREA, Self::default())
+        };
+        Self {
+            scale: (area.x / width.max(1f32)).max(area.y / height.max(1f32)),
+            ..layout
+        }
+    }
+
+    pub fn slot_position(&self, inventory_index: usize) -> Vec3 {
+        let offset =
+            self.inventory - Vec2::new((self.columns - 1) as f32 * self.spacing / 2f32, 0f32);
+        let x = inventory_index % self.columns;
+        let y = inventory_index / self.columns;
+        (Vec2::new(x as f32 * self.spacing, -(y as f32) * self.spacing) + offset).extend(20f32)
+    }
+
+    pub fn operation_position(&self, index: usize, count: usize) -> Vec3 {
+        let offset =
+            self.operations - Vec2::new(count.saturating_sub(1) as f32 * self.spacing / 2f32, 0f32);
+        (Vec2::new(index as f32 * self.spacing, 0f32) + offset).extend(20f32)
+    }
+}
+
+fn fit_to_window(
+    mut resized: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut layout: ResMut<BoardLayout>,
+    mut q_projection: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut fitted: Local<bool>,
+) {
+    let primary_resized = resized
+        .iter()
+        .filter(|event| event.id == WindowId::primary())
+        .count()
+        > 0;
+    if *fitted && !primary_resized {
+        return;
+    }
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    *fitted = true;
+    *layout = BoardLayout::for_window(window.width(), window.height());
+    for mut projection in &mut q_projection {
+        projection.scale = layout.scale;
+    }
+}
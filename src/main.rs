@@ -1,30 +1,70 @@
+mod clock;
 mod cursor;
 mod done;
+mod expression;
 mod game;
+mod input;
+mod layout;
 mod menu;
 mod particles;
+mod pause;
+mod picking;
+mod random;
+mod rules;
+mod settings;
+mod solver;
+mod sound;
+mod storage;
+mod theme;
+mod tween;
+mod ui;
 
-use bevy::prelude::*;
+use bevy::{asset::AssetServerSettings, prelude::*};
 use bevy_jornet::{JornetPlugin, Leaderboard};
+use clock::ClockPlugin;
 use cursor::{CursorPlugin, MainCamera};
 use done::DonePlugin;
 use game::GamePlugin;
+use input::InputPlugin;
+use layout::LayoutPlugin;
 use menu::MenuPlugin;
 use particles::ParticlesPlugin;
+use pause::PausePlugin;
+use picking::PickingPlugin;
+use settings::SettingsPlugin;
+use sound::SoundPlugin;
+use theme::ThemePlugin;
+use tween::TweenPlugin;
+use ui::WidgetsPlugin;
 
 fn main() {
     App::new()
+        .insert_resource(AssetServerSettings {
+            // Hot-reload themes and other assets while the game runs.
+            watch_for_changes: cfg!(not(target_arch = "wasm32")),
+            ..default()
+        })
         .add_plugins(DefaultPlugins)
         .add_plugin(JornetPlugin::with_leaderboard(
             option_env!("JORNET_LEADERBOARD_ID").unwrap_or("5d4b1eb9-0ca8-40a5-84ec-77551b8632b6"),
             option_env!("JORNET_LEADERBOARD_SECRET")
                 .unwrap_or("5577ec38-4a60-46eb-8498-b810323fccb8"),
         ))
+        .add_plugin(SettingsPlugin)
+        .add_plugin(ThemePlugin)
+        .add_plugin(WidgetsPlugin)
+        .add_plugin(ClockPlugin)
+        .add_plugin(TweenPlugin)
+        .add_plugin(InputPlugin)
+        .add_plugin(LayoutPlugin)
         .add_plugin(ParticlesPlugin)
+        .add_plugin(SoundPlugin)
         .add_plugin(CursorPlugin)
+        .add_plugin(PickingPlugin)
         .add_plugin(GamePlugin)
         .add_plugin(MenuPlugin)
         .add_plugin(DonePlugin)
+        .add_plugin(PausePlugin)
         .add_state(GameState::Menu)
         .add_startup_system(setup)
         .run();
@@ -35,6 +75,8 @@ enum GameState {
     Menu,
     Game,
     Done,
+    Settings,
+    Paused,
 }
 
 struct TextFont(pub Handle<Font>);
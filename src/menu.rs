@@ -1,13 +1,15 @@
 use bevy::prelude::*;
 use bevy_jornet::Leaderboard;
 
-use crate::{game::Level, GameState, TextFont};
+use crate::{
+    game::Level,
+    settings::Settings,
+    theme::{Theme, ThemeColor, Themed},
+    ui::{self, ButtonAction, ButtonPressed, Skin},
+    GameState, TextFont,
+};
 pub struct MenuPlugin;
 
-const BACKGROUND: &str = "339755";
-const BUTTON: &str = "2A4747";
-const TEXT: &str = "BeDaD6";
-
 #[derive(Component)]
 struct MenuUI;
 
@@ -16,10 +18,12 @@ impl Plugin for MenuPlugin {
         app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(display_menu))
             .add_system_set(
                 SystemSet::on_update(GameState::Menu)
-                    .with_system(button_system)
+                    .with_system(handle_buttons)
                     .with_system(display_scores),
             )
-            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(despawn_menu));
+            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(despawn_menu))
+            .add_system_set(SystemSet::on_pause(GameState::Menu).with_system(despawn_menu))
+            .add_system_set(SystemSet::on_resume(GameState::Menu).with_system(display_menu));
     }
 }
 
@@ -28,124 +32,71 @@ fn display_menu(
     font: Res<TextFont>,
     leaderboard: Res<Leaderboard>,
     level: Res<Level>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
 ) {
-    commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                margin: UiRect::all(Val::Auto),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                flex_direction: FlexDirection::ColumnReverse,
-                border: UiRect::all(Val::Px(30.0)),
-                ..default()
-            },
-            color: Color::hex(BACKGROUND).unwrap().into(),
-            ..default()
-        })
+    let skin = Skin {
+        font: &font,
+        settings: &settings,
+        theme: &theme,
+    };
+    skin.spawn_panel(&mut commands, ThemeColor::Background, 30.0)
         .insert(MenuUI)
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle::from_section(
-                "Math it",
-                TextStyle {
-                    font: font.0.clone(),
-                    font_size: 60.0,
-                    color: Color::hex(TEXT).unwrap(),
-                },
-            ));
-            parent.spawn_bundle(TextBundle::from_section(
-                format!("Level {}", level.level_index + 1),
-                TextStyle {
-                    font: font.0.clone(),
-                    font_size: 30.0,
-                    color: Color::hex(TEXT).unwrap(),
-                },
-            ));
+            skin.label(parent, "Math it", 60.0);
+            skin.label(parent, format!("Level {}", level.level_index + 1), 30.0);
             parent
-                .spawn_bundle(NodeBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Row,
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        margin: UiRect::all(Val::Px(20.0)),
-                        ..default()
-                    },
-                    color: Color::NONE.into(),
-                    ..default()
+                .spawn_bundle(ui::row())
+                .insert(Style {
+                    margin: UiRect::all(Val::Px(20.0)),
+                    ..ui::row().style
                 })
                 .with_children(|parent| {
                     parent
-                        .spawn_bundle(NodeBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(300.0), Val::Undefined),
-                                flex_direction: FlexDirection::ColumnReverse,
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                margin: UiRect::all(Val::Px(20.0)),
-                                ..default()
-                            },
-                            color: Color::NONE.into(),
-                            ..default()
+                        .spawn_bundle(ui::column())
+                        .insert(Style {
+                            size: Size::new(Val::Px(300.0), Val::Undefined),
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..ui::column().style
                         })
                         .insert(LeaderboardMarker::Player);
                     parent
-                        .spawn_bundle(NodeBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(150.0), Val::Undefined),
-                                flex_direction: FlexDirection::ColumnReverse,
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                margin: UiRect::all(Val::Px(20.0)),
-                                ..default()
-                            },
-                            color: Color::NONE.into(),
-                            ..default()
+                        .spawn_bundle(ui::column())
+                        .insert(Style {
+                            size: Size::new(Val::Px(150.0), Val::Undefined),
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..ui::column().style
                         })
                         .insert(LeaderboardMarker::Score);
                 });
-
-            parent
-                .spawn_bundle(ButtonBundle {
-                    style: Style {
-                        size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    color: Color::hex(BUTTON).unwrap().into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn_bundle(TextBundle::from_section(
-                        "Play",
-                        TextStyle {
-                            font: font.0.clone(),
-                            font_size: 40.0,
-                            color: Color::hex(TEXT).unwrap(),
-                        },
-                    ));
-                });
+            skin.button(
+                parent,
+                "Play",
+                ButtonAction::Play,
+                Size::new(Val::Px(200.0), Val::Px(65.0)),
+                40.0,
+            );
+            skin.button(
+                parent,
+                "Settings",
+                ButtonAction::Settings,
+                Size::new(Val::Px(200.0), Val::Px(65.0)),
+                40.0,
+            );
         });
     commands
         .spawn_bundle(
             TextBundle::from_sections([
                 TextSection {
                     value: "you are: ".to_string(),
-                    style: TextStyle {
-                        font: font.0.clone(),
-                        font_size: 20.0,
-                        color: Color::hex(TEXT).unwrap(),
-                    },
+                    style: skin.text_style(20.0),
                 },
                 TextSection {
                     value: leaderboard
                         .get_player()
                         .map(|p| p.name.clone())
                         .unwrap_or_default(),
-                    style: TextStyle {
-                        font: font.0.clone(),
-                        font_size: 25.0,
-                        color: Color::hex(TEXT).unwrap(),
-                    },
+                    style: skin.text_style(25.0),
                 },
             ])
             .with_style(Style {
@@ -159,7 +110,8 @@ fn display_menu(
             }),
         )
         .insert(MenuUI)
-        .insert(PlayerName);
+        .insert(PlayerName)
+        .insert(Themed(ThemeColor::Text));
 
     leaderboard.refresh_leaderboard();
 }
@@ -177,10 +129,17 @@ fn display_scores(
     leaderboard: Res<Leaderboard>,
     mut commands: Commands,
     font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
     root_ui: Query<(Entity, &LeaderboardMarker)>,
     mut player_name: Query<&mut Text, With<PlayerName>>,
 ) {
     if leaderboard.is_changed() {
+        let skin = Skin {
+            font: &font,
+            settings: &settings,
+            theme: &theme,
+        };
         if let Some(player) = leaderboard.get_player() {
             player_name.single_mut().sections[1].value = player.name.clone();
         }
@@ -202,17 +161,14 @@ fn display_scores(
             commands.entity(root_entity).despawn_descendants();
             for score in &leaderboard {
                 commands.entity(root_entity).with_children(|parent| {
-                    parent.spawn_bundle(TextBundle::from_section(
+                    skin.label(
+                        parent,
                         match marker {
                             LeaderboardMarker::Score => format!("{} ", score.score),
                             LeaderboardMarker::Player => score.player.clone(),
                         },
-                        TextStyle {
-                            font: font.0.clone(),
-                            font_size: 30.0,
-                            color: Color::hex(TEXT).unwrap(),
-                        },
-                    ));
+                        30.0,
+                    );
                 });
             }
         }
@@ -228,25 +184,12 @@ fn despawn_menu(
     }
 }
 
-fn button_system(
-    mut interaction_query: Query<
-        (&Interaction, &mut UiColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-    mut state: ResMut<State<GameState>>,
-) {
-    for (interaction, mut color) in &mut interaction_query {
-        match *interaction {
-            Interaction::Clicked => {
-                *color = (Color::hex(BUTTON).unwrap() + Color::GRAY).into();
-                let _ = state.set(GameState::Game);
-            }
-            Interaction::Hovered => {
-                *color = (Color::hex(BUTTON).unwrap() + Color::DARK_GRAY).into();
-            }
-            Interaction::None => {
-                *color = Color::hex(BUTTON).unwrap().into();
-            }
-        }
+fn handle_buttons(mut pressed: EventReader<ButtonPressed>, mut state: ResMut<State<GameState>>) {
+    for ButtonPressed(action) in pressed.iter() {
+        let _ = match action {
+            ButtonAction::Play => state.set(GameState::Game),
+            ButtonAction::Settings => state.push(GameState::Settings),
+            _ => continue,
+        };
     }
 }
@@ -1,74 +1,278 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
 use rand::Rng;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    clock::{GameClock, Lifetime},
+    settings::Settings,
+    tween::lerp_color,
+};
+
+/// Most particles alive at once, across all bursts.
+const PARTICLE_BUDGET: usize = 400;
 
 pub struct ParticlesPlugin;
 
 impl Plugin for ParticlesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ParticleExplosion>()
-            .add_system(update_velocity)
-            .add_system(destroy_after)
+        app.add_asset::<ParticlePresets>()
+            .init_asset_loader::<ParticlePresetsLoader>()
+            .add_event::<ParticleExplosion>()
+            .init_resource::<ParticlePool>()
+            .add_startup_system(load_presets)
+            .add_system(update_particles)
             .add_system(handle_particle_events);
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleEffect {
+    Success,
+    Error,
+    Win,
+    Confetti,
+}
+
 #[derive(Debug)]
 pub struct ParticleExplosion {
     pub location: Vec2,
-    pub color: Color,
+    pub effect: ParticleEffect,
 }
 
-#[derive(Component)]
-pub struct DestroyAfter {
-    time_to_destroy: f32,
+/// How a burst of particles looks, loaded from `assets/particles/effects.particles.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParticlePreset {
+    pub count: u32,
+    /// Ranges are picked from uniformly for each particle.
+    pub size: (f32, f32),
+    pub speed: (f32, f32),
+    /// Direction of the initial velocity in degrees, counter-clockwise from the right.
+    pub angle: (f32, f32),
+    /// Radius around the burst location where particles appear.
+    pub spread: f32,
+    /// Downwards acceleration in pixels per second squared.
+    pub gravity: f32,
+    /// Fraction of the velocity lost per second.
+    pub drag: f32,
+    pub lifetime: (f32, f32),
+    pub fade_out: bool,
+    pub shrink: bool,
+    /// Colours over the lifetime of a particle, evenly spaced.
+    #[serde(deserialize_with = "hex_colors")]
+    pub colors: Vec<Color>,
+    /// Each particle keeps one of the colours at random instead of going through them.
+    #[serde(default)]
+    pub pick_color: bool,
+}
+
+impl ParticlePreset {
+    fn color_at(&self, progress: f32) -> Color {
+        match self.colors.len() {
+            0 => Color::WHITE,
+            1 => self.colors[0],
+            count => {
+                let position = progress.clamp(0f32, 1f32) * (count - 1) as f32;
+                let index = (position.floor() as usize).min(count - 2);
+                lerp_color(
+                    self.colors[index],
+                    self.colors[index + 1],
+                    position - index as f32,
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "c1d7a4e2-5b3f-4a8e-9f60-2d8b7e1a4c95"]
+pub struct ParticlePresets {
+    pub success: ParticlePreset,
+    pub error: ParticlePreset,
+    pub win: ParticlePreset,
+    pub confetti: ParticlePreset,
+}
+
+impl ParticlePresets {
+    pub fn get(&self, effect: ParticleEffect) -> &ParticlePreset {
+        match effect {
+            ParticleEffect::Success => &self.success,
+            ParticleEffect::Error => &self.error,
+            ParticleEffect::Win => &self.win,
+            ParticleEffect::Confetti => &self.confetti,
+        }
+    }
+}
+
+fn hex_colors<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Color>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|hex| Color::hex(hex).map_err(serde::de::Error::custom))
+        .collect()
 }
 
-impl DestroyAfter {
-    pub fn new(time_to_destroy: f32) -> Self {
-        Self { time_to_destroy }
+#[derive(Default)]
+struct ParticlePresetsLoader;
+
+impl AssetLoader for ParticlePresetsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let presets = ron::de::from_bytes::<ParticlePresets>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(presets));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["particles.ron"]
     }
 }
 
+struct PresetsHandle(Handle<ParticlePresets>);
+
+/// Particle entities are never despawned: finished ones are hidden and reused by later bursts.
+#[derive(Default)]
+struct ParticlePool {
+    free: Vec<Entity>,
+    spawned: usize,
+}
+
 #[derive(Component)]
-pub struct Velocity(pub Vec2);
+struct Particle {
+    effect: ParticleEffect,
+    /// Set when the preset picks a colour per particle.
+    color: Option<Color>,
+    velocity: Vec2,
+    size: f32,
+    active: bool,
+}
 
-fn update_velocity(time: Res<Time>, mut q_vel: Query<(&mut Transform, &Velocity)>) {
-    for (mut t, vel) in q_vel.iter_mut() {
-        t.translation += (vel.0 * time.delta_seconds()).extend(0f32);
+fn load_presets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PresetsHandle(
+        asset_server.load("particles/effects.particles.ron"),
+    ));
+}
+
+fn random_in(rng: &mut impl Rng, (min, max): (f32, f32)) -> f32 {
+    if max > min {
+        rng.gen_range(min..max)
+    } else {
+        min
     }
 }
 
-fn destroy_after(mut commands: Commands, time: Res<Time>, q_des: Query<(Entity, &DestroyAfter)>) {
-    for (e, d) in q_des.iter() {
-        if d.time_to_destroy <= time.seconds_since_startup() as f32 {
-            commands.entity(e).despawn();
+/// Moves, fades and recycles all particles at once.
+fn update_particles(
+    clock: Res<GameClock>,
+    handle: Res<PresetsHandle>,
+    presets: Res<Assets<ParticlePresets>>,
+    mut pool: ResMut<ParticlePool>,
+    mut q_particles: Query<(
+        Entity,
+        &mut Particle,
+        &Lifetime,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+) {
+    let presets = match presets.get(&handle.0) {
+        Some(presets) => presets,
+        None => return,
+    };
+    let delta = clock.delta_seconds();
+    for (e, mut particle, lifetime, mut transform, mut sprite, mut visibility) in &mut q_particles {
+        if !particle.active {
+            continue;
+        }
+        let preset = presets.get(particle.effect);
+        if lifetime.timer.finished() {
+            particle.active = false;
+            visibility.is_visible = false;
+            pool.free.push(e);
+            continue;
+        }
+        let progress = lifetime.timer.percent();
+        particle.velocity.y -= preset.gravity * delta;
+        particle.velocity *= (1f32 - preset.drag * delta).max(0f32);
+        transform.translation += (particle.velocity * delta).extend(0f32);
+
+        let mut color = particle.color.unwrap_or_else(|| preset.color_at(progress));
+        if preset.fade_out {
+            color.set_a(color.a() * (1f32 - progress));
         }
+        sprite.color = color;
+        let size = if preset.shrink {
+            particle.size * (1f32 - progress)
+        } else {
+            particle.size
+        };
+        sprite.custom_size = Some(Vec2::splat(size));
     }
 }
 
 fn handle_particle_events(
     mut commands: Commands,
-    time: Res<Time>,
+    mut pool: ResMut<ParticlePool>,
+    settings: Res<Settings>,
+    handle: Res<PresetsHandle>,
+    presets: Res<Assets<ParticlePresets>>,
     mut evt_particles: EventReader<ParticleExplosion>,
 ) {
+    let mut rng = rand::thread_rng();
     for p in evt_particles.iter() {
-        let time_to_die = time.seconds_since_startup() as f32 + 1f32;
-        for i in 0..40 {
-            let mut offset: Vec2 = rand::thread_rng().gen::<(f32, f32)>().into();
-            offset -= Vec2::new(0.5f32, 0.5f32);
-            let position = p.location + (offset * 50f32);
+        if settings.reduced_motion {
+            continue;
+        }
+        let preset = match presets.get(&handle.0) {
+            Some(presets) => presets.get(p.effect),
+            None => continue,
+        };
+        for _ in 0..preset.count {
+            let angle = random_in(&mut rng, preset.angle).to_radians();
+            let direction = Vec2::new(angle.cos(), angle.sin());
+            let position = p.location
+                + Vec2::new(rng.gen_range(-1f32..=1f32), rng.gen_range(-1f32..=1f32))
+                    * preset.spread;
+            let size = random_in(&mut rng, preset.size);
+            let color = (preset.pick_color && !preset.colors.is_empty())
+                .then(|| preset.colors[rng.gen_range(0..preset.colors.len())]);
+            let entity = match pool.free.pop() {
+                Some(entity) => entity,
+                None if pool.spawned < PARTICLE_BUDGET => {
+                    pool.spawned += 1;
+                    commands.spawn().id()
+                }
+                // Over budget: the rest of the burst is dropped.
+                None => break,
+            };
             commands
-                .spawn_bundle(SpriteBundle {
+                .entity(entity)
+                .insert_bundle(SpriteBundle {
                     sprite: Sprite {
-                        color: p.color,
-                        custom_size: Some(Vec2::splat(10f32)),
+                        color: color.unwrap_or_else(|| preset.color_at(0f32)),
+                        custom_size: Some(Vec2::splat(size)),
                         ..default()
                     },
                     transform: Transform::from_translation(position.extend(1f32)),
                     ..default()
                 })
-                .insert(Velocity(offset * 2000f32))
-                .insert(DestroyAfter::new(time_to_die));
+                .insert(Particle {
+                    effect: p.effect,
+                    color,
+                    velocity: direction * random_in(&mut rng, preset.speed),
+                    size,
+                    active: true,
+                })
+                .insert(Lifetime::new(random_in(&mut rng, preset.lifetime)));
         }
     }
 }
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::{
+    clock::GameClock,
+    game::RestartLevel,
+    input::{self, ActionPressed, InputAction},
+    settings::Settings,
+    theme::{Theme, ThemeColor},
+    ui::{ButtonAction, ButtonPressed, Skin},
+    GameState, TextFont,
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Paused)
+                .with_system(display_menu)
+                .with_system(stop_clock)
+                .with_system(input::clear_actions),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Paused)
+                .with_system(handle_buttons)
+                .with_system(handle_actions),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Paused)
+                .with_system(despawn_menu)
+                .with_system(start_clock),
+        )
+        .add_system_set(SystemSet::on_pause(GameState::Paused).with_system(despawn_menu))
+        .add_system_set(
+            SystemSet::on_resume(GameState::Paused)
+                .with_system(display_menu)
+                .with_system(input::clear_actions),
+        );
+    }
+}
+
+#[derive(Component)]
+struct PauseUI;
+
+fn display_menu(
+    mut commands: Commands,
+    font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+) {
+    let skin = Skin {
+        font: &font,
+        settings: &settings,
+        theme: &theme,
+    };
+    let button_size = Size::new(Val::Px(350.0), Val::Px(65.0));
+    skin.spawn_panel(&mut commands, ThemeColor::Background, 30.0)
+        .insert(PauseUI)
+        .with_children(|parent| {
+            skin.label(parent, "Paused", 60.0);
+            skin.button(parent, "Resume", ButtonAction::Resume, button_size, 40.0);
+            skin.button(
+                parent,
+                "Restart level",
+                ButtonAction::Restart,
+                button_size,
+                40.0,
+            );
+            skin.button(
+                parent,
+                "Settings",
+                ButtonAction::Settings,
+                button_size,
+                40.0,
+            );
+            skin.button(
+                parent,
+                "Quit to menu",
+                ButtonAction::Quit,
+                button_size,
+                40.0,
+            );
+        });
+}
+
+fn stop_clock(mut clock: ResMut<GameClock>) {
+    clock.paused = true;
+}
+
+fn start_clock(mut clock: ResMut<GameClock>) {
+    clock.paused = false;
+}
+
+fn despawn_menu(
+    mut commands: Commands,
+    root_ui: Query<Entity, (With<Node>, With<PauseUI>, Without<Parent>)>,
+) {
+    for entity in &root_ui {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_buttons(
+    mut pressed: EventReader<ButtonPressed>,
+    mut restart: EventWriter<RestartLevel>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for ButtonPressed(action) in pressed.iter() {
+        let _ = match action {
+            ButtonAction::Resume => state.pop(),
+            ButtonAction::Restart => {
+                restart.send(RestartLevel);
+                state.pop()
+            }
+            ButtonAction::Settings => state.push(GameState::Settings),
+            ButtonAction::Quit => state.replace(GameState::Menu),
+            _ => continue,
+        };
+    }
+}
+
+/// The pause input toggles the menu, and backing out of it resumes too.
+fn handle_actions(mut pressed: EventReader<ActionPressed>, mut state: ResMut<State<GameState>>) {
+    for ActionPressed(action) in pressed.iter() {
+        if matches!(action, InputAction::Pause | InputAction::Deselect) {
+            let _ = state.pop();
+        }
+    }
+}
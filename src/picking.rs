@@ -0,0 +1,88 @@
+use bevy::{prelude::*, text::Text2dSize};
+
+use crate::{
+    cursor::{self, Pointers},
+    ui::Disabled,
+};
+
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hover>()
+            .add_event::<HoverEvent>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_hover.after(cursor::track_pointers),
+            );
+    }
+}
+
+/// World entity that can be pointed at, within the bounds of its text or sprite.
+///
+/// Ignored while hidden or [`Disabled`].
+#[derive(Component, Default)]
+pub struct Pickable {
+    /// Bounds are grown to at least this size, so small glyphs stay easy to hit.
+    pub min_size: Vec2,
+}
+
+/// Topmost pickable entity under the primary pointer.
+#[derive(Default)]
+pub struct Hover(pub Option<Entity>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoverEvent {
+    Enter(Entity),
+    Leave(Entity),
+}
+
+fn update_hover(
+    pointers: Res<Pointers>,
+    mut hover: ResMut<Hover>,
+    mut hover_events: EventWriter<HoverEvent>,
+    q_pickables: Query<
+        (
+            Entity,
+            &Pickable,
+            &GlobalTransform,
+            Option<&Text2dSize>,
+            Option<&Sprite>,
+            &Visibility,
+        ),
+        Without<Disabled>,
+    >,
+) {
+    let mut topmost: Option<(Entity, f32)> = None;
+    if let Some(pointer) = pointers.primary() {
+        for (entity, pickable, transform, text_size, sprite, visibility) in &q_pickables {
+            if !visibility.is_visible {
+                continue;
+            }
+            let size = text_size
+                .map(|text| text.size)
+                .or_else(|| sprite.and_then(|sprite| sprite.custom_size))
+                .unwrap_or_default()
+                .max(pickable.min_size);
+            let (scale, _, translation) = transform.to_scale_rotation_translation();
+            let half_size = size * scale.truncate().abs() / 2f32;
+            let offset = (pointer - translation.truncate()).abs();
+            if offset.x > half_size.x || offset.y > half_size.y {
+                continue;
+            }
+            if topmost.map_or(true, |(_, z)| translation.z > z) {
+                topmost = Some((entity, translation.z));
+            }
+        }
+    }
+    let hovered = topmost.map(|(entity, _)| entity);
+    if hovered != hover.0 {
+        if let Some(entity) = hover.0 {
+            hover_events.send(HoverEvent::Leave(entity));
+        }
+        if let Some(entity) = hovered {
+            hover_events.send(HoverEvent::Enter(entity));
+        }
+        hover.0 = hovered;
+    }
+}
@@ -0,0 +1,109 @@
+/// Random numbers that come out the same on every platform and across dependency updates.
+///
+/// This is PCG32 (XSH RR), using only fixed-width integer arithmetic so that native and
+/// wasm builds generate identical puzzles from the same seed.
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+impl Pcg32 {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+
+    /// Uniform in `0..bound`, without modulo bias.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "empty range");
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = self.next_u32();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Uniform in `min..=max`.
+    pub fn between(&mut self, min: u32, max: u32) -> u32 {
+        min + self.below(max - min + 1)
+    }
+
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u32) as usize
+    }
+
+    /// Two different indexes below `len`, in random order.
+    pub fn two_indexes(&mut self, len: usize) -> (usize, usize) {
+        let first = self.index(len);
+        let mut second = self.index(len - 1);
+        if second >= first {
+            second += 1;
+        }
+        (first, second)
+    }
+}
+
+/// Mixes a value so that close inputs give unrelated outputs (SplitMix64 finaliser).
+pub fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Seed for one level of a run, independent from the seeds of its neighbours.
+pub fn level_seed(seed: u64, level_index: u64) -> u64 {
+    mix(seed ^ mix(level_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcg32_matches_reference_outputs() {
+        // First outputs of the reference implementation's `pcg32_srandom(42, 54)`.
+        let mut rng = Pcg32::new(42, 54);
+        let outputs: Vec<u32> = (0..6).map(|_| rng.next_u32()).collect();
+        assert_eq!(
+            outputs,
+            [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]
+        );
+    }
+
+    #[test]
+    fn mix_matches_splitmix64() {
+        // SplitMix64 seeded with 0 first outputs this.
+        assert_eq!(mix(0), 0xe220a8397b1dcdaf);
+        assert_eq!(mix(1), 0x910a2dec89025cc1);
+        assert_eq!(level_seed(42, 3), 0x43aa8652ad94b3a2);
+    }
+
+    #[test]
+    fn two_indexes_are_different() {
+        let mut rng = Pcg32::new(7, 0);
+        for len in 2..20 {
+            let (first, second) = rng.two_indexes(len);
+            assert_ne!(first, second);
+            assert!(first < len && second < len);
+        }
+    }
+}
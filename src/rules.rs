@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// How levels are played, picked in the settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Combine every number into one, as close to the target as possible.
+    Classic,
+    /// Stop as soon as any number reaches the target, or submit the closest one.
+    Countdown,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Classic, GameMode::Countdown];
+
+    pub fn rules(&self, arithmetic: Arithmetic) -> Rules {
+        match self {
+            GameMode::Classic => Rules {
+                finish_on_target: false,
+                can_submit: false,
+                unused_number_points: 0,
+                arithmetic,
+            },
+            GameMode::Countdown => Rules {
+                finish_on_target: true,
+                can_submit: true,
+                unused_number_points: 10,
+                arithmetic,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// Reaching the target with any number ends the level.
+    pub finish_on_target: bool,
+    /// The player may end the level with the closest number they have.
+    pub can_submit: bool,
+    /// Added to the score for each number left at the end, negative to penalise them.
+    pub unused_number_points: i32,
+    pub arithmetic: Arithmetic,
+}
+
+/// Which results operations may produce, from the generator to the player's rounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Arithmetic {
+    /// Any non-zero divisor, so fractions can show up.
+    Fractions,
+    /// Division only when there is no remainder.
+    Integers,
+    /// Like integers, and subtraction never goes below zero.
+    Naturals,
+}
+
+impl Arithmetic {
+    pub const ALL: [Arithmetic; 3] = [
+        Arithmetic::Fractions,
+        Arithmetic::Integers,
+        Arithmetic::Naturals,
+    ];
+
+    pub fn exact_division(&self) -> bool {
+        *self != Arithmetic::Fractions
+    }
+
+    pub fn allows_negative(&self) -> bool {
+        *self != Arithmetic::Naturals
+    }
+}
@@ -0,0 +1,558 @@
+use bevy::{prelude::*, ui::FocusPolicy, window::WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rules::{Arithmetic, GameMode, Rules},
+    storage,
+    theme::{Theme, ThemeColor, Themed, THEMES},
+    ui::{self, ButtonAction, ButtonPressed, Skin},
+    GameState, TextFont,
+};
+
+const SETTINGS_KEY: &str = "settings";
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Settings>(SETTINGS_KEY).unwrap_or_default())
+            .add_system(apply_window_settings)
+            .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(display_settings))
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(handle_buttons)
+                    .with_system(slider_system)
+                    .with_system(update_values)
+                    .with_system(rebuild_on_style_change),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings)
+                    .with_system(despawn_settings)
+                    .with_system(save_settings),
+            );
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+    pub fullscreen: bool,
+    pub window_size: WindowSize,
+    pub theme: String,
+    pub text_scale: f32,
+    pub reduced_motion: bool,
+    pub number_format: NumberFormat,
+    /// Selecting with a full selection replaces its oldest part instead of being refused.
+    pub replace_oldest_selection: bool,
+    /// Complete rounds wait for a confirmation before being played.
+    pub careful_mode: bool,
+    pub chain_mode: ChainMode,
+    pub game_mode: GameMode,
+    /// Arithmetic picked for each mode, fractions for the others.
+    pub arithmetic_by_mode: Vec<(GameMode, Arithmetic)>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 0.8,
+            music_volume: 0.6,
+            effects_volume: 0.8,
+            fullscreen: false,
+            window_size: WindowSize::Medium,
+            theme: THEMES[0].to_string(),
+            text_scale: 1f32,
+            reduced_motion: false,
+            number_format: NumberFormat::Decimal,
+            replace_oldest_selection: false,
+            careful_mode: false,
+            chain_mode: ChainMode::Off,
+            game_mode: GameMode::Classic,
+            arithmetic_by_mode: vec![],
+        }
+    }
+}
+
+impl Settings {
+    /// The rules of the chosen mode, with the arithmetic picked for it.
+    pub fn rules(&self) -> Rules {
+        self.game_mode.rules(self.arithmetic(self.game_mode))
+    }
+
+    pub fn arithmetic(&self, mode: GameMode) -> Arithmetic {
+        self.arithmetic_by_mode
+            .iter()
+            .find(|(picked_mode, _)| *picked_mode == mode)
+            .map_or(Arithmetic::Fractions, |(_, arithmetic)| *arithmetic)
+    }
+
+    fn set_arithmetic(&mut self, mode: GameMode, arithmetic: Arithmetic) {
+        self.arithmetic_by_mode
+            .retain(|(picked_mode, _)| *picked_mode != mode);
+        self.arithmetic_by_mode.push((mode, arithmetic));
+    }
+
+    pub fn font_size(&self, base: f32) -> f32 {
+        base * self.text_scale
+    }
+
+    fn volume(&self, kind: SettingKind) -> Option<f32> {
+        match kind {
+            SettingKind::MasterVolume => Some(self.master_volume),
+            SettingKind::MusicVolume => Some(self.music_volume),
+            SettingKind::EffectsVolume => Some(self.effects_volume),
+            _ => None,
+        }
+    }
+
+    fn set_volume(&mut self, kind: SettingKind, volume: f32) {
+        let volume = volume.clamp(0f32, 1f32);
+        match kind {
+            SettingKind::MasterVolume => self.master_volume = volume,
+            SettingKind::MusicVolume => self.music_volume = volume,
+            SettingKind::EffectsVolume => self.effects_volume = volume,
+            _ => {}
+        }
+    }
+
+    fn adjust(&mut self, kind: SettingKind, step: i32) {
+        match kind {
+            SettingKind::MasterVolume | SettingKind::MusicVolume | SettingKind::EffectsVolume => {
+                let volume = self.volume(kind).unwrap_or_default() + step as f32 * 0.1f32;
+                self.set_volume(kind, (volume * 10f32).round() / 10f32);
+            }
+            SettingKind::Fullscreen => self.fullscreen = !self.fullscreen,
+            SettingKind::WindowSize => {
+                self.window_size = cycle(&WindowSize::ALL, self.window_size, step)
+            }
+            SettingKind::Theme => {
+                self.theme = cycle(&THEMES, self.theme.as_str(), step).to_string()
+            }
+            SettingKind::TextScale => {
+                self.text_scale = (self.text_scale + step as f32 * 0.25f32).clamp(0.75f32, 1.5f32)
+            }
+            SettingKind::ReducedMotion => self.reduced_motion = !self.reduced_motion,
+            SettingKind::NumberFormat => {
+                self.number_format = cycle(&NumberFormat::ALL, self.number_format, step)
+            }
+            SettingKind::ReplaceSelection => {
+                self.replace_oldest_selection = !self.replace_oldest_selection
+            }
+            SettingKind::CarefulMode => self.careful_mode = !self.careful_mode,
+            SettingKind::ChainMode => {
+                self.chain_mode = cycle(&ChainMode::ALL, self.chain_mode, step)
+            }
+            SettingKind::GameMode => self.game_mode = cycle(&GameMode::ALL, self.game_mode, step),
+            SettingKind::Arithmetic => {
+                let arithmetic = cycle(&Arithmetic::ALL, self.arithmetic(self.game_mode), step);
+                self.set_arithmetic(self.game_mode, arithmetic);
+            }
+        }
+    }
+
+    fn describe(&self, kind: SettingKind) -> String {
+        match kind {
+            SettingKind::MasterVolume | SettingKind::MusicVolume | SettingKind::EffectsVolume => {
+                format!("{:.0}%", self.volume(kind).unwrap_or_default() * 100f32)
+            }
+            SettingKind::Fullscreen => on_off(self.fullscreen),
+            SettingKind::WindowSize => {
+                let (width, height) = self.window_size.resolution();
+                format!("{width}x{height}")
+            }
+            SettingKind::Theme => self.theme.replace('_', " "),
+            SettingKind::TextScale => format!("{:.0}%", self.text_scale * 100f32),
+            SettingKind::ReducedMotion => on_off(self.reduced_motion),
+            SettingKind::NumberFormat => format!("{:?}", self.number_format),
+            SettingKind::ReplaceSelection => on_off(self.replace_oldest_selection),
+            SettingKind::CarefulMode => on_off(self.careful_mode),
+            SettingKind::ChainMode => format!("{:?}", self.chain_mode),
+            SettingKind::GameMode => format!("{:?}", self.game_mode),
+            SettingKind::Arithmetic => format!("{:?}", self.arithmetic(self.game_mode)),
+        }
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "On" } else { "Off" }.to_string()
+}
+
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T, step: i32) -> T {
+    let index = all.iter().position(|v| *v == current).unwrap_or_default() as i32;
+    all[(index + step).rem_euclid(all.len() as i32) as usize]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl WindowSize {
+    const ALL: [WindowSize; 3] = [WindowSize::Small, WindowSize::Medium, WindowSize::Large];
+
+    pub fn resolution(&self) -> (f32, f32) {
+        match self {
+            WindowSize::Small => (800f32, 600f32),
+            WindowSize::Medium => (1280f32, 720f32),
+            WindowSize::Large => (1600f32, 900f32),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumberFormat {
+    Decimal,
+    Fraction,
+}
+
+impl NumberFormat {
+    const ALL: [NumberFormat; 2] = [NumberFormat::Decimal, NumberFormat::Fraction];
+
+    pub fn format(&self, number: f32) -> String {
+        if let NumberFormat::Fraction = self {
+            if let Some((numerator, denominator)) = to_fraction(number) {
+                return if denominator == 1 {
+                    format!("{numerator}")
+                } else {
+                    format!("{numerator}/{denominator}")
+                };
+            }
+        }
+        format!("{number:.1}").trim_end_matches(".0").to_string()
+    }
+}
+
+/// What stays selected after a combination, to carry on calculating from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChainMode {
+    Off,
+    /// The result becomes the first number of the next round.
+    Result,
+    Operation,
+    Both,
+}
+
+impl ChainMode {
+    const ALL: [ChainMode; 4] = [
+        ChainMode::Off,
+        ChainMode::Result,
+        ChainMode::Operation,
+        ChainMode::Both,
+    ];
+
+    pub fn keeps_result(&self) -> bool {
+        matches!(self, ChainMode::Result | ChainMode::Both)
+    }
+
+    pub fn keeps_operation(&self) -> bool {
+        matches!(self, ChainMode::Operation | ChainMode::Both)
+    }
+}
+
+/// Finds the simplest fraction close to `number` using continued fractions.
+fn to_fraction(number: f32) -> Option<(i64, i64)> {
+    let value = (number as f64).abs();
+    let sign = if number < 0f32 { -1 } else { 1 };
+    let (mut h0, mut h1, mut k0, mut k1) = (0i64, 1i64, 1i64, 0i64);
+    let mut x = value;
+    for _ in 0..20 {
+        let a = x.floor() as i64;
+        let (h2, k2) = (a * h1 + h0, a * k1 + k0);
+        if k2 > 1000 {
+            break;
+        }
+        (h0, h1, k0, k1) = (h1, h2, k1, k2);
+        if (h1 as f64 / k1 as f64 - value).abs() < 1e-4 {
+            return Some((sign * h1, k1));
+        }
+        let remainder = x - a as f64;
+        if remainder < 1e-9 {
+            break;
+        }
+        x = 1f64 / remainder;
+    }
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingKind {
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    Fullscreen,
+    WindowSize,
+    Theme,
+    TextScale,
+    ReducedMotion,
+    NumberFormat,
+    ReplaceSelection,
+    CarefulMode,
+    ChainMode,
+    GameMode,
+    Arithmetic,
+}
+
+impl SettingKind {
+    const ALL: [SettingKind; 14] = [
+        SettingKind::MasterVolume,
+        SettingKind::MusicVolume,
+        SettingKind::EffectsVolume,
+        SettingKind::Fullscreen,
+        SettingKind::WindowSize,
+        SettingKind::Theme,
+        SettingKind::TextScale,
+        SettingKind::ReducedMotion,
+        SettingKind::NumberFormat,
+        SettingKind::ReplaceSelection,
+        SettingKind::CarefulMode,
+        SettingKind::ChainMode,
+        SettingKind::GameMode,
+        SettingKind::Arithmetic,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SettingKind::MasterVolume => "Master volume",
+            SettingKind::MusicVolume => "Music volume",
+            SettingKind::EffectsVolume => "Effects volume",
+            SettingKind::Fullscreen => "Fullscreen",
+            SettingKind::WindowSize => "Window size",
+            SettingKind::Theme => "Colour theme",
+            SettingKind::TextScale => "Text size",
+            SettingKind::ReducedMotion => "Reduced motion",
+            SettingKind::NumberFormat => "Numbers",
+            SettingKind::ReplaceSelection => "Replace oldest selection",
+            SettingKind::CarefulMode => "Confirm combinations",
+            SettingKind::ChainMode => "Keep selected",
+            SettingKind::GameMode => "Mode",
+            SettingKind::Arithmetic => "Arithmetic",
+        }
+    }
+
+    fn is_volume(&self) -> bool {
+        matches!(
+            self,
+            SettingKind::MasterVolume | SettingKind::MusicVolume | SettingKind::EffectsVolume
+        )
+    }
+}
+
+#[derive(Component)]
+struct SettingsUI {
+    text_scale: f32,
+}
+
+#[derive(Component)]
+struct SettingValue(SettingKind);
+
+#[derive(Component)]
+struct VolumeSlider(SettingKind);
+
+#[derive(Component)]
+struct SliderFill(SettingKind);
+
+fn display_settings(
+    mut commands: Commands,
+    font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+) {
+    let skin = Skin {
+        font: &font,
+        settings: &settings,
+        theme: &theme,
+    };
+    spawn_settings(&mut commands, &skin);
+}
+
+fn spawn_settings(commands: &mut Commands, skin: &Skin) {
+    let settings = skin.settings;
+    let small = Size::new(Val::Px(40.0), Val::Px(40.0));
+    skin.spawn_panel(commands, ThemeColor::Background, 30.0)
+        .insert(SettingsUI {
+            text_scale: settings.text_scale,
+        })
+        .with_children(|parent| {
+            skin.label(parent, "Settings", 60.0);
+            for kind in SettingKind::ALL {
+                parent.spawn_bundle(ui::row()).with_children(|parent| {
+                    skin.label(parent, kind.label(), 25.0).insert(Style {
+                        size: Size::new(Val::Px(250.0), Val::Undefined),
+                        ..default()
+                    });
+                    skin.button(
+                        parent,
+                        "<",
+                        ButtonAction::AdjustSetting(kind, -1),
+                        small,
+                        30.0,
+                    );
+                    if let Some(volume) = settings.volume(kind) {
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(150.0), Val::Px(20.0)),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..default()
+                                },
+                                color: skin.theme.button.into(),
+                                ..default()
+                            })
+                            .insert(Interaction::default())
+                            .insert(VolumeSlider(kind))
+                            .insert(Themed(ThemeColor::Button))
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(NodeBundle {
+                                        style: Style {
+                                            size: Size::new(
+                                                Val::Percent(volume * 100.0),
+                                                Val::Percent(100.0),
+                                            ),
+                                            ..default()
+                                        },
+                                        color: skin.theme.text.into(),
+                                        ..default()
+                                    })
+                                    .insert(FocusPolicy::Pass)
+                                    .insert(SliderFill(kind))
+                                    .insert(Themed(ThemeColor::Text));
+                            });
+                    }
+                    skin.label(parent, settings.describe(kind), 25.0)
+                        .insert(Style {
+                            size: Size::new(
+                                Val::Px(if kind.is_volume() { 70.0 } else { 150.0 }),
+                                Val::Undefined,
+                            ),
+                            ..default()
+                        })
+                        .insert(SettingValue(kind));
+                    skin.button(
+                        parent,
+                        ">",
+                        ButtonAction::AdjustSetting(kind, 1),
+                        small,
+                        30.0,
+                    );
+                });
+            }
+            skin.button(
+                parent,
+                "Back",
+                ButtonAction::Back,
+                Size::new(Val::Px(150.0), Val::Px(65.0)),
+                40.0,
+            );
+        });
+}
+
+fn despawn_settings(
+    mut commands: Commands,
+    root_ui: Query<Entity, (With<Node>, With<SettingsUI>, Without<Parent>)>,
+) {
+    for entity in &root_ui {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    storage::save(SETTINGS_KEY, &*settings);
+}
+
+fn rebuild_on_style_change(
+    mut commands: Commands,
+    font: Res<TextFont>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    root_ui: Query<(Entity, &SettingsUI), (With<Node>, Without<Parent>)>,
+) {
+    for (entity, ui) in &root_ui {
+        if ui.text_scale != settings.text_scale {
+            commands.entity(entity).despawn_recursive();
+            let skin = Skin {
+                font: &font,
+                settings: &settings,
+                theme: &theme,
+            };
+            spawn_settings(&mut commands, &skin);
+        }
+    }
+}
+
+fn handle_buttons(
+    mut pressed: EventReader<ButtonPressed>,
+    mut settings: ResMut<Settings>,
+    mut state: ResMut<State<GameState>>,
+) {
+    for ButtonPressed(action) in pressed.iter() {
+        match *action {
+            ButtonAction::AdjustSetting(kind, step) => settings.adjust(kind, step),
+            ButtonAction::Back => {
+                let _ = state.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn slider_system(
+    windows: Res<Windows>,
+    mut settings: ResMut<Settings>,
+    q_sliders: Query<(&Interaction, &Node, &GlobalTransform, &VolumeSlider)>,
+) {
+    let cursor = match windows.get_primary().and_then(|w| w.cursor_position()) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    for (interaction, node, transform, slider) in &q_sliders {
+        if *interaction == Interaction::Clicked && node.size.x > 0f32 {
+            let left = transform.translation().x - node.size.x / 2f32;
+            let volume = ((cursor.x - left) / node.size.x).clamp(0f32, 1f32);
+            if settings.volume(slider.0) != Some(volume) {
+                settings.set_volume(slider.0, volume);
+            }
+        }
+    }
+}
+
+fn update_values(
+    settings: Res<Settings>,
+    mut q_values: Query<(&mut Text, &SettingValue)>,
+    mut q_fills: Query<(&mut Style, &SliderFill)>,
+) {
+    if settings.is_changed() {
+        for (mut text, value) in &mut q_values {
+            text.sections[0].value = settings.describe(value.0);
+        }
+        for (mut style, fill) in &mut q_fills {
+            style.size.width = Val::Percent(settings.volume(fill.0).unwrap_or_default() * 100.0);
+        }
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: ResMut<Windows>,
+    mut applied: Local<Option<(bool, WindowSize)>>,
+) {
+    let wanted = Some((settings.fullscreen, settings.window_size));
+    if *applied == wanted {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        if settings.fullscreen {
+            window.set_mode(WindowMode::BorderlessFullscreen);
+        } else {
+            window.set_mode(WindowMode::Windowed);
+            let (width, height) = settings.window_size.resolution();
+            window.set_resolution(width, height);
+        }
+        *applied = wanted;
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{game::Operation, rules::Arithmetic};
+
+/// How many positions a hint search may explore before settling for its best find.
+const SEARCH_BUDGET: usize = 50_000;
+
+/// One combination of two inventory numbers, as the player would play it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub first: usize,
+    pub second: usize,
+    pub operation: Operation,
+}
+
+/// Looks for the next step towards reaching `target` with the remaining `numbers`.
+///
+/// Searches for an exact solution first, falling back to the closest result found
+/// within the search budget. Runs a slice at a time, so that it never stalls a frame.
+pub struct Search {
+    numbers: Vec<f32>,
+    operations: Vec<Operation>,
+    arithmetic: Arithmetic,
+    target: f32,
+    budget: usize,
+    stack: Vec<Position>,
+    best: Option<(f32, Step)>,
+}
+
+/// Numbers left after the steps explored so far, and the next combination to try on them.
+struct Position {
+    numbers: Vec<f32>,
+    first_step: Option<Step>,
+    first: usize,
+    second: usize,
+    operation: usize,
+}
+
+impl Search {
+    pub fn new(
+        numbers: &[f32],
+        operations: &[Operation],
+        arithmetic: Arithmetic,
+        target: f32,
+    ) -> Self {
+        let mut search = Self {
+            numbers: numbers.to_vec(),
+            operations: operations.to_vec(),
+            arithmetic,
+            target,
+            budget: SEARCH_BUDGET,
+            stack: Vec::new(),
+            best: None,
+        };
+        search.push(numbers.to_vec(), None);
+        search
+    }
+
+    /// Numbers the search started from.
+    pub fn numbers(&self) -> &[f32] {
+        &self.numbers
+    }
+
+    /// Closest step found so far.
+    pub fn best(&self) -> Option<Step> {
+        self.best.as_ref().map(|(_, step)| step.clone())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Explores up to `positions` more positions, returning true once the search is over.
+    pub fn run(&mut self, mut positions: usize) -> bool {
+        while positions > 0 {
+            let position = match self.stack.last_mut() {
+                Some(position) => position,
+                None => break,
+            };
+            match next_combination(position, &self.operations, self.arithmetic) {
+                None => {
+                    self.stack.pop();
+                }
+                Some((numbers, step)) => {
+                    let first_step = position.first_step.clone().unwrap_or(step);
+                    if let [last] = numbers[..] {
+                        let distance = (last - self.target).abs();
+                        if self
+                            .best
+                            .as_ref()
+                            .map_or(true, |(best_distance, _)| distance < *best_distance)
+                        {
+                            self.best = Some((distance, first_step));
+                        }
+                        if distance == 0f32 {
+                            self.stack.clear();
+                        }
+                    } else if self.push(numbers, Some(first_step)) {
+                        positions -= 1;
+                    }
+                }
+            }
+        }
+        self.is_finished()
+    }
+
+    /// Returns false once the budget is spent.
+    fn push(&mut self, numbers: Vec<f32>, first_step: Option<Step>) -> bool {
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+        self.stack.push(Position {
+            numbers,
+            first_step,
+            first: 0,
+            second: 0,
+            operation: 0,
+        });
+        true
+    }
+}
+
+/// Combines the next two numbers of `position`, skipping the combinations that fail.
+fn next_combination(
+    position: &mut Position,
+    operations: &[Operation],
+    arithmetic: Arithmetic,
+) -> Option<(Vec<f32>, Step)> {
+    let len = position.numbers.len();
+    while !operations.is_empty() && position.first < len {
+        let (first, second) = (position.first, position.second);
+        let operation = &operations[position.operation];
+        position.operation += 1;
+        if position.operation == operations.len() {
+            position.operation = 0;
+            position.second += 1;
+            if position.second == len {
+                position.second = 0;
+                position.first += 1;
+            }
+        }
+        let commutative = matches!(operation, Operation::Plus | Operation::Multiply);
+        if first == second || (commutative && first > second) {
+            continue;
+        }
+        if let Ok(result) = operation.apply(
+            position.numbers[first],
+            position.numbers[second],
+            arithmetic,
+        ) {
+            let mut next = position.numbers.clone();
+            next[first] = result;
+            next.remove(second);
+            let step = Step {
+                first,
+                second,
+                operation: operation.clone(),
+            };
+            return Some((next, step));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_OPERATIONS: [Operation; 4] = [
+        Operation::Plus,
+        Operation::Minus,
+        Operation::Multiply,
+        Operation::Divide,
+    ];
+
+    fn step(first: usize, second: usize, operation: Operation) -> Step {
+        Step {
+            first,
+            second,
+            operation,
+        }
+    }
+
+    #[test]
+    fn finds_an_exact_solution() {
+        // (3 + 4) * 5
+        let mut search = Search::new(
+            &[3f32, 4f32, 5f32],
+            &ALL_OPERATIONS,
+            Arithmetic::Naturals,
+            35f32,
+        );
+        assert!(search.run(usize::MAX));
+        assert_eq!(search.best(), Some(step(0, 1, Operation::Plus)));
+        assert_eq!(
+            search.best.as_ref().map(|(distance, _)| *distance),
+            Some(0f32)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_closest_value() {
+        let operations = [Operation::Plus, Operation::Multiply];
+        let mut search = Search::new(&[2f32, 3f32], &operations, Arithmetic::Naturals, 10f32);
+        assert!(search.run(usize::MAX));
+        assert_eq!(search.best(), Some(step(0, 1, Operation::Multiply)));
+    }
+
+    #[test]
+    fn stops_once_the_budget_is_spent() {
+        let numbers = [1f32, 2f32, 3f32, 5f32, 7f32, 11f32];
+        let mut search = Search::new(&numbers, &ALL_OPERATIONS, Arithmetic::Fractions, 100_000f32);
+        search.budget = 10;
+        assert!(search.run(usize::MAX));
+        assert_eq!(search.budget, 0);
+        assert!(search.best().is_some());
+    }
+
+    #[test]
+    fn runs_a_slice_at_a_time() {
+        let numbers = [1f32, 2f32, 3f32, 5f32, 7f32, 11f32];
+        let mut whole = Search::new(&numbers, &ALL_OPERATIONS, Arithmetic::Integers, 997f32);
+        whole.run(usize::MAX);
+        let mut sliced = Search::new(&numbers, &ALL_OPERATIONS, Arithmetic::Integers, 997f32);
+        let mut slices = 0;
+        while !sliced.run(100) {
+            slices += 1;
+        }
+        assert!(slices > 1);
+        assert_eq!(sliced.best(), whole.best());
+    }
+}
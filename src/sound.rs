@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{GameEvent, LevelOutcome},
+    picking::HoverEvent,
+    settings::Settings,
+    GameState,
+};
+
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Music>()
+            .add_startup_system(load_sounds)
+            .add_system(play_game_sounds)
+            .add_system(play_button_sounds)
+            .add_system(play_hover_sounds)
+            .add_system(switch_music)
+            .add_system(crossfade_music.after(switch_music));
+    }
+}
+
+struct Sounds {
+    select_number: Handle<AudioSource>,
+    select_operation: Handle<AudioSource>,
+    deselect: Handle<AudioSource>,
+    combine: Handle<AudioSource>,
+    invalid: Handle<AudioSource>,
+    won: Handle<AudioSource>,
+    close: Handle<AudioSource>,
+    failed: Handle<AudioSource>,
+    hover: Handle<AudioSource>,
+    click: Handle<AudioSource>,
+    music_menu: Handle<AudioSource>,
+    music_game: Handle<AudioSource>,
+    music_done: Handle<AudioSource>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MusicTrack {
+    Menu,
+    Game,
+    Done,
+}
+
+impl MusicTrack {
+    fn for_state(state: &GameState) -> Self {
+        match state {
+            GameState::Menu | GameState::Settings => MusicTrack::Menu,
+            GameState::Game | GameState::Paused => MusicTrack::Game,
+            GameState::Done => MusicTrack::Done,
+        }
+    }
+}
+
+struct PlayingMusic {
+    sink: Handle<AudioSink>,
+    fade: f32,
+}
+
+#[derive(Default)]
+struct Music {
+    track: Option<MusicTrack>,
+    current: Option<PlayingMusic>,
+    fading_out: Vec<PlayingMusic>,
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sounds {
+        select_number: asset_server.load("sounds/select_number.wav"),
+        select_operation: asset_server.load("sounds/select_operation.wav"),
+        deselect: asset_server.load("sounds/deselect.wav"),
+        combine: asset_server.load("sounds/combine.wav"),
+        invalid: asset_server.load("sounds/invalid.wav"),
+        won: asset_server.load("sounds/won.wav"),
+        close: asset_server.load("sounds/close.wav"),
+        failed: asset_server.load("sounds/failed.wav"),
+        hover: asset_server.load("sounds/hover.wav"),
+        click: asset_server.load("sounds/click.wav"),
+        music_menu: asset_server.load("music/menu.wav"),
+        music_game: asset_server.load("music/game.wav"),
+        music_done: asset_server.load("music/done.wav"),
+    });
+}
+
+fn play_effect(audio: &Audio, settings: &Settings, sound: &Handle<AudioSource>) {
+    let volume = settings.master_volume * settings.effects_volume;
+    if volume > 0f32 {
+        audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(volume));
+    }
+}
+
+fn play_game_sounds(
+    audio: Res<Audio>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut game_events: EventReader<GameEvent>,
+) {
+    for event in game_events.iter() {
+        let sound = match event {
+            GameEvent::NumberSelected | GameEvent::Swapped => &sounds.select_number,
+            GameEvent::OperationSelected => &sounds.select_operation,
+            GameEvent::Deselected | GameEvent::Undone | GameEvent::RestartRequested => {
+                &sounds.deselect
+            }
+            GameEvent::Combined => &sounds.combine,
+            GameEvent::InvalidOperation { .. }
+            | GameEvent::SelectionFull
+            | GameEvent::NothingToSubmit => &sounds.invalid,
+            GameEvent::LevelFinished(LevelOutcome::Won) => &sounds.won,
+            GameEvent::LevelFinished(LevelOutcome::Close) => &sounds.close,
+            GameEvent::LevelFinished(LevelOutcome::Failed) => &sounds.failed,
+        };
+        play_effect(&audio, &settings, sound);
+    }
+}
+
+fn play_button_sounds(
+    audio: Res<Audio>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    q_buttons: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+) {
+    for interaction in &q_buttons {
+        match interaction {
+            Interaction::Clicked => play_effect(&audio, &settings, &sounds.click),
+            Interaction::Hovered => play_effect(&audio, &settings, &sounds.hover),
+            Interaction::None => {}
+        }
+    }
+}
+
+fn play_hover_sounds(
+    audio: Res<Audio>,
+    sounds: Res<Sounds>,
+    settings: Res<Settings>,
+    mut hover_events: EventReader<HoverEvent>,
+) {
+    for event in hover_events.iter() {
+        if let HoverEvent::Enter(_) = event {
+            play_effect(&audio, &settings, &sounds.hover);
+        }
+    }
+}
+
+fn switch_music(
+    audio: Res<Audio>,
+    sounds: Res<Sounds>,
+    state: Res<State<GameState>>,
+    sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<Music>,
+) {
+    let track = MusicTrack::for_state(state.current());
+    if music.track == Some(track) {
+        return;
+    }
+    music.track = Some(track);
+    if let Some(previous) = music.current.take() {
+        music.fading_out.push(previous);
+    }
+    let source = match track {
+        MusicTrack::Menu => &sounds.music_menu,
+        MusicTrack::Game => &sounds.music_game,
+        MusicTrack::Done => &sounds.music_done,
+    };
+    let sink = audio.play_with_settings(source.clone(), PlaybackSettings::LOOP.with_volume(0f32));
+    music.current = Some(PlayingMusic {
+        sink: sinks.get_handle(sink),
+        fade: 0f32,
+    });
+}
+
+fn crossfade_music(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    sinks: Res<Assets<AudioSink>>,
+    mut music: ResMut<Music>,
+) {
+    let step = time.delta_seconds() / CROSSFADE_SECONDS;
+    let volume = settings.master_volume * settings.music_volume;
+    let music = music.as_mut();
+    if let Some(current) = &mut music.current {
+        current.fade = (current.fade + step).min(1f32);
+        if let Some(sink) = sinks.get(&current.sink) {
+            sink.set_volume(current.fade * volume);
+        }
+    }
+    for previous in &mut music.fading_out {
+        previous.fade = (previous.fade - step).max(0f32);
+        if let Some(sink) = sinks.get(&previous.sink) {
+            sink.set_volume(previous.fade * volume);
+            // Dropping the last handle detaches the sink rather than stopping it.
+            if previous.fade == 0f32 {
+                sink.stop();
+            }
+        }
+    }
+    music.fading_out.retain(|previous| previous.fade > 0f32);
+}
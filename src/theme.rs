@@ -0,0 +1,200 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Deserializer};
+
+use crate::settings::Settings;
+
+/// Built-in themes, loaded from `assets/themes/<name>.theme.ron`.
+pub const THEMES: [&str; 4] = ["classic", "night", "high_contrast", "colorblind"];
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<Theme>()
+            .add_startup_system(load_themes)
+            .add_system(select_theme)
+            .add_system(apply_theme.after(select_theme));
+    }
+}
+
+/// Colours used across the game. The active one is available as a resource.
+#[derive(Debug, Clone, PartialEq, Deserialize, TypeUuid)]
+#[uuid = "7b4e2f0c-31a8-4d62-9a57-0e5c8d1f6b93"]
+pub struct Theme {
+    #[serde(deserialize_with = "hex")]
+    pub background: Color,
+    #[serde(deserialize_with = "hex")]
+    pub background_won: Color,
+    #[serde(deserialize_with = "hex")]
+    pub background_close: Color,
+    #[serde(deserialize_with = "hex")]
+    pub background_failed: Color,
+    #[serde(deserialize_with = "hex")]
+    pub button: Color,
+    #[serde(deserialize_with = "hex")]
+    pub button_hovered: Color,
+    #[serde(deserialize_with = "hex")]
+    pub button_pressed: Color,
+    #[serde(deserialize_with = "hex")]
+    pub button_disabled: Color,
+    #[serde(deserialize_with = "hex")]
+    pub text: Color,
+    #[serde(deserialize_with = "hex")]
+    pub board_text: Color,
+    #[serde(deserialize_with = "hex")]
+    pub clear_color: Color,
+    #[serde(deserialize_with = "hex")]
+    pub selection_first: Color,
+    #[serde(deserialize_with = "hex")]
+    pub selection_second: Color,
+    #[serde(deserialize_with = "hex")]
+    pub selection_operation: Color,
+    /// Behind slots and operations under the pointer, usually translucent.
+    #[serde(deserialize_with = "hex")]
+    pub selection_hover: Color,
+    /// Numbers and operations on top of a selection colour, readable against all three.
+    #[serde(deserialize_with = "hex")]
+    pub selection_text: Color,
+}
+
+impl Default for Theme {
+    /// Used until the selected theme is loaded.
+    fn default() -> Self {
+        ron::from_str(include_str!("../assets/themes/classic.theme.ron"))
+            .expect("built-in theme should be valid")
+    }
+}
+
+impl Theme {
+    pub fn color(&self, color: ThemeColor) -> Color {
+        match color {
+            ThemeColor::Background => self.background,
+            ThemeColor::BackgroundWon => self.background_won,
+            ThemeColor::BackgroundClose => self.background_close,
+            ThemeColor::BackgroundFailed => self.background_failed,
+            ThemeColor::Button => self.button,
+            ThemeColor::Text => self.text,
+            ThemeColor::BoardText => self.board_text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+    Background,
+    BackgroundWon,
+    BackgroundClose,
+    BackgroundFailed,
+    Button,
+    Text,
+    BoardText,
+}
+
+/// Keeps an entity's [`UiColor`] or [`Text`] colour in sync with the active [`Theme`].
+#[derive(Component)]
+pub struct Themed(pub ThemeColor);
+
+fn hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Color::hex(&hex).map_err(serde::de::Error::custom)
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let theme = ron::de::from_bytes::<Theme>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+struct ThemeHandles(Vec<(&'static str, Handle<Theme>)>);
+
+impl ThemeHandles {
+    fn get(&self, name: &str) -> Option<&Handle<Theme>> {
+        self.0
+            .iter()
+            .find(|(theme_name, _)| *theme_name == name)
+            .map(|(_, handle)| handle)
+    }
+}
+
+fn load_themes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ThemeHandles(
+        THEMES
+            .iter()
+            .map(|name| {
+                (
+                    *name,
+                    asset_server.load(format!("themes/{name}.theme.ron").as_str()),
+                )
+            })
+            .collect(),
+    ));
+}
+
+fn select_theme(
+    settings: Res<Settings>,
+    handles: Res<ThemeHandles>,
+    themes: Res<Assets<Theme>>,
+    mut theme_events: EventReader<AssetEvent<Theme>>,
+    mut theme: ResMut<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let selected = handles.get(&settings.theme);
+    let reloaded = theme_events
+        .iter()
+        .filter(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                Some(handle) == selected
+            }
+            AssetEvent::Removed { .. } => false,
+        })
+        .count()
+        > 0;
+    if !settings.is_changed() && !reloaded {
+        return;
+    }
+    if let Some(loaded) = selected.and_then(|handle| themes.get(handle)) {
+        if *theme != *loaded {
+            *theme = loaded.clone();
+            clear_color.0 = theme.clear_color;
+        }
+    }
+}
+
+fn apply_theme(
+    theme: Res<Theme>,
+    mut q_nodes: Query<(&mut UiColor, &Themed), Without<Text>>,
+    mut q_texts: Query<(&mut Text, &Themed)>,
+) {
+    if theme.is_changed() {
+        for (mut color, themed) in &mut q_nodes {
+            *color = theme.color(themed.0).into();
+        }
+        for (mut text, themed) in &mut q_texts {
+            for section in &mut text.sections {
+                section.style.color = theme.color(themed.0);
+            }
+        }
+    }
+}
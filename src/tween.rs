@@ -0,0 +1,373 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{clock::GameClock, settings::Settings};
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TweenFinished>()
+            .add_system(animate::<Translation>)
+            .add_system(animate::<Rotation>)
+            .add_system(animate::<Scale>)
+            .add_system(animate::<SpriteColor>)
+            .add_system(animate::<NodeColor>)
+            .add_system(animate::<TextAlpha>);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ease {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    /// Overshoots the end value a little before settling on it.
+    BackOut,
+}
+
+impl Ease {
+    /// Maps linear progress between 0 and 1 to eased progress.
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => t * (2f32 - t),
+            Ease::QuadInOut => {
+                if t < 0.5f32 {
+                    2f32 * t * t
+                } else {
+                    -1f32 + (4f32 - 2f32 * t) * t
+                }
+            }
+            Ease::BackOut => {
+                let overshoot = 1.70158f32;
+                let t = t - 1f32;
+                1f32 + (overshoot + 1f32) * t.powi(3) + overshoot * t.powi(2)
+            }
+        }
+    }
+}
+
+/// A value of a component that a [`Tween`] can animate.
+pub trait Lens: Send + Sync + 'static {
+    const KIND: TweenLens;
+    type Target: Component;
+    type Value: Copy + Send + Sync + 'static;
+
+    fn get(target: &Self::Target) -> Self::Value;
+    fn set(target: &mut Self::Target, value: Self::Value);
+    fn lerp(from: Self::Value, to: Self::Value, t: f32) -> Self::Value;
+}
+
+/// Tells the lenses apart in [`TweenFinished`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenLens {
+    Translation,
+    Rotation,
+    Scale,
+    SpriteColor,
+    NodeColor,
+    TextAlpha,
+}
+
+/// Sent when a [`Tween`] reaches the end of its last step.
+pub struct TweenFinished {
+    pub entity: Entity,
+    pub lens: TweenLens,
+}
+
+pub struct Translation;
+
+impl Lens for Translation {
+    const KIND: TweenLens = TweenLens::Translation;
+    type Target = Transform;
+    type Value = Vec3;
+
+    fn get(target: &Transform) -> Vec3 {
+        target.translation
+    }
+
+    fn set(target: &mut Transform, value: Vec3) {
+        target.translation = value;
+    }
+
+    fn lerp(from: Vec3, to: Vec3, t: f32) -> Vec3 {
+        from.lerp(to, t)
+    }
+}
+
+pub struct Scale;
+
+impl Lens for Scale {
+    const KIND: TweenLens = TweenLens::Scale;
+    type Target = Transform;
+    type Value = Vec3;
+
+    fn get(target: &Transform) -> Vec3 {
+        target.scale
+    }
+
+    fn set(target: &mut Transform, value: Vec3) {
+        target.scale = value;
+    }
+
+    fn lerp(from: Vec3, to: Vec3, t: f32) -> Vec3 {
+        from.lerp(to, t)
+    }
+}
+
+pub struct Rotation;
+
+impl Lens for Rotation {
+    const KIND: TweenLens = TweenLens::Rotation;
+    type Target = Transform;
+    type Value = Quat;
+
+    fn get(target: &Transform) -> Quat {
+        target.rotation
+    }
+
+    fn set(target: &mut Transform, value: Quat) {
+        target.rotation = value;
+    }
+
+    fn lerp(from: Quat, to: Quat, t: f32) -> Quat {
+        from.slerp(to, t)
+    }
+}
+
+pub fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    let color = from.lerp(to, t);
+    Color::rgba(color.x, color.y, color.z, color.w)
+}
+
+pub struct SpriteColor;
+
+impl Lens for SpriteColor {
+    const KIND: TweenLens = TweenLens::SpriteColor;
+    type Target = Sprite;
+    type Value = Color;
+
+    fn get(target: &Sprite) -> Color {
+        target.color
+    }
+
+    fn set(target: &mut Sprite, value: Color) {
+        target.color = value;
+    }
+
+    fn lerp(from: Color, to: Color, t: f32) -> Color {
+        lerp_color(from, to, t)
+    }
+}
+
+/// Background colour of UI nodes.
+pub struct NodeColor;
+
+impl Lens for NodeColor {
+    const KIND: TweenLens = TweenLens::NodeColor;
+    type Target = UiColor;
+    type Value = Color;
+
+    fn get(target: &UiColor) -> Color {
+        target.0
+    }
+
+    fn set(target: &mut UiColor, value: Color) {
+        target.0 = value;
+    }
+
+    fn lerp(from: Color, to: Color, t: f32) -> Color {
+        lerp_color(from, to, t)
+    }
+}
+
+/// Opacity of every section of a text, keeping their colours.
+pub struct TextAlpha;
+
+impl Lens for TextAlpha {
+    const KIND: TweenLens = TweenLens::TextAlpha;
+    type Target = Text;
+    type Value = f32;
+
+    fn get(target: &Text) -> f32 {
+        target
+            .sections
+            .first()
+            .map_or(1f32, |section| section.style.color.a())
+    }
+
+    fn set(target: &mut Text, value: f32) {
+        for section in &mut target.sections {
+            section.style.color.set_a(value);
+        }
+    }
+
+    fn lerp(from: f32, to: f32, t: f32) -> f32 {
+        from + (to - from) * t
+    }
+}
+
+/// Animates the value seen through `L` through a sequence of steps.
+///
+/// Inserting a new tween replaces the running one, which continues from wherever it was.
+/// With reduced motion, steps jump to their end value once their delay is over.
+/// Runs on the [`GameClock`] unless [`Tween::unscaled`].
+#[derive(Component)]
+pub struct Tween<L: Lens> {
+    steps: Vec<Step<L::Value>>,
+    current: usize,
+    elapsed: f32,
+    despawn: bool,
+    unscaled: bool,
+    _lens: PhantomData<L>,
+}
+
+struct Step<V> {
+    /// Continues from the current value when not set.
+    from: Option<V>,
+    to: V,
+    duration: f32,
+    delay: f32,
+    ease: Ease,
+}
+
+impl<L: Lens> Tween<L> {
+    /// Starts from the current value.
+    pub fn to(to: L::Value, duration: f32) -> Self {
+        Self {
+            steps: vec![Step {
+                from: None,
+                to,
+                duration,
+                delay: 0f32,
+                ease: Ease::QuadInOut,
+            }],
+            current: 0,
+            elapsed: 0f32,
+            despawn: false,
+            unscaled: false,
+            _lens: PhantomData,
+        }
+    }
+
+    /// Jumps to `from` once the delay is over.
+    pub fn from_to(from: L::Value, to: L::Value, duration: f32) -> Self {
+        let mut tween = Self::to(to, duration);
+        tween.steps[0].from = Some(from);
+        tween
+    }
+
+    /// Continues to `to` after the previous step.
+    pub fn then(mut self, to: L::Value, duration: f32) -> Self {
+        self.steps.push(Step {
+            from: None,
+            to,
+            duration,
+            delay: 0f32,
+            ease: Ease::QuadInOut,
+        });
+        self
+    }
+
+    /// Applies to the last step.
+    pub fn with_ease(mut self, ease: Ease) -> Self {
+        self.last_step().ease = ease;
+        self
+    }
+
+    /// Applies to the last step.
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.last_step().delay = delay;
+        self
+    }
+
+    /// Despawns the entity once finished.
+    pub fn despawning(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+
+    /// Runs on real time, for menus that animate while the game is paused.
+    pub fn unscaled(mut self) -> Self {
+        self.unscaled = true;
+        self
+    }
+
+    /// Value the tween will settle on.
+    pub fn end_value(&self) -> L::Value {
+        self.steps[self.steps.len() - 1].to
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+
+    fn last_step(&mut self) -> &mut Step<L::Value> {
+        let last = self.steps.len() - 1;
+        &mut self.steps[last]
+    }
+}
+
+fn animate<L: Lens>(
+    mut commands: Commands,
+    time: Res<Time>,
+    clock: Res<GameClock>,
+    settings: Res<Settings>,
+    mut finished: EventWriter<TweenFinished>,
+    mut q_tweens: Query<(Entity, &mut L::Target, &mut Tween<L>)>,
+) {
+    for (entity, mut target, mut tween) in &mut q_tweens {
+        if tween.is_finished() {
+            continue;
+        }
+        let tween = &mut *tween;
+        tween.elapsed += if tween.unscaled {
+            time.delta_seconds()
+        } else {
+            clock.delta_seconds()
+        };
+        // Go through as many steps as the elapsed time covers.
+        while !tween.is_finished() {
+            let elapsed = tween.elapsed;
+            let step = &mut tween.steps[tween.current];
+            if elapsed < step.delay {
+                break;
+            }
+            let duration = if settings.reduced_motion {
+                0f32
+            } else {
+                step.duration
+            };
+            let progress = if duration <= 0f32 {
+                1f32
+            } else {
+                ((elapsed - step.delay) / duration).min(1f32)
+            };
+            let from = *step.from.get_or_insert_with(|| L::get(&*target));
+            L::set(
+                &mut *target,
+                L::lerp(from, step.to, step.ease.apply(progress)),
+            );
+            if progress < 1f32 {
+                break;
+            }
+            tween.elapsed -= step.delay + duration;
+            tween.current += 1;
+        }
+        if tween.is_finished() {
+            finished.send(TweenFinished {
+                entity,
+                lens: L::KIND,
+            });
+            if tween.despawn {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
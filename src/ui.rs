@@ -0,0 +1,294 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use crate::{
+    settings::{SettingKind, Settings},
+    theme::{Theme, ThemeColor, Themed},
+    tween::{Ease, Scale, Tween},
+    GameState, TextFont,
+};
+
+pub struct WidgetsPlugin;
+
+impl Plugin for WidgetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ButtonPressed>()
+            .add_event::<UiNavigation>()
+            .init_resource::<UiFocus>()
+            .add_system(keyboard_navigation)
+            .add_system(button_interaction)
+            .add_system(navigate_focus.after(keyboard_navigation))
+            .add_system(
+                button_colors
+                    .after(button_interaction)
+                    .after(navigate_focus),
+            );
+    }
+}
+
+/// What a [`UiButton`] does when pressed, handled by the screen owning it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    Play,
+    Settings,
+    Back,
+    NextLevel,
+    Retry,
+    Pause,
+    Resume,
+    Restart,
+    Quit,
+    Submit,
+    AdjustSetting(SettingKind, i32),
+}
+
+#[derive(Component)]
+pub struct UiButton {
+    pub action: ButtonAction,
+}
+
+/// Buttons with this component are greyed out and can't be pressed.
+#[derive(Component)]
+pub struct Disabled;
+
+/// Sent when a [`UiButton`] is clicked, or activated through focus navigation.
+pub struct ButtonPressed(pub ButtonAction);
+
+pub enum UiNavigation {
+    Previous,
+    Next,
+    Activate,
+}
+
+/// Button currently focused by keyboard or gamepad navigation.
+#[derive(Default)]
+pub struct UiFocus(pub Option<Entity>);
+
+/// Shared look of the screens, borrowed from the current resources.
+pub struct Skin<'a> {
+    pub font: &'a TextFont,
+    pub settings: &'a Settings,
+    pub theme: &'a Theme,
+}
+
+impl Skin<'_> {
+    pub fn text_style(&self, size: f32) -> TextStyle {
+        TextStyle {
+            font: self.font.0.clone(),
+            font_size: self.settings.font_size(size),
+            color: self.theme.text,
+        }
+    }
+
+    /// Spawns a centered column with a themed background, to hold a whole screen.
+    pub fn spawn_panel<'w, 's, 'c>(
+        &self,
+        commands: &'c mut Commands<'w, 's>,
+        background: ThemeColor,
+        border: f32,
+    ) -> EntityCommands<'w, 's, 'c> {
+        let mut panel = commands.spawn_bundle(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                border: UiRect::all(Val::Px(border)),
+                ..default()
+            },
+            color: self.theme.color(background).into(),
+            ..default()
+        });
+        panel.insert(Themed(background)).insert(
+            Tween::<Scale>::from_to(Vec3::splat(0.9), Vec3::ONE, 0.25)
+                .with_ease(Ease::BackOut)
+                .unscaled(),
+        );
+        panel
+    }
+
+    pub fn label<'w, 's, 'c>(
+        &self,
+        parent: &'c mut ChildBuilder<'w, 's, '_>,
+        text: impl Into<String>,
+        size: f32,
+    ) -> EntityCommands<'w, 's, 'c> {
+        let mut label = parent.spawn_bundle(TextBundle::from_section(text, self.text_style(size)));
+        label.insert(Themed(ThemeColor::Text));
+        label
+    }
+
+    pub fn button<'w, 's, 'c>(
+        &self,
+        parent: &'c mut ChildBuilder<'w, 's, '_>,
+        text: impl Into<String>,
+        action: ButtonAction,
+        size: Size<Val>,
+        font_size: f32,
+    ) -> EntityCommands<'w, 's, 'c> {
+        let mut button = parent.spawn_bundle(ButtonBundle {
+            style: Style {
+                size,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                margin: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            color: self.theme.button.into(),
+            ..default()
+        });
+        button.insert(UiButton { action }).with_children(|parent| {
+            self.label(parent, text, font_size);
+        });
+        button
+    }
+}
+
+/// Transparent node laying out its children in a row.
+pub fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+/// Transparent node laying out its children from top to bottom.
+pub fn column() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    }
+}
+
+fn keyboard_navigation(keys: Res<Input<KeyCode>>, mut navigation: EventWriter<UiNavigation>) {
+    let backwards = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    if keys.just_pressed(KeyCode::Up)
+        || keys.just_pressed(KeyCode::Left)
+        || (backwards && keys.just_pressed(KeyCode::Tab))
+    {
+        navigation.send(UiNavigation::Previous);
+    }
+    if keys.just_pressed(KeyCode::Down)
+        || keys.just_pressed(KeyCode::Right)
+        || (!backwards && keys.just_pressed(KeyCode::Tab))
+    {
+        navigation.send(UiNavigation::Next);
+    }
+    if keys.just_pressed(KeyCode::Return) || keys.just_pressed(KeyCode::Space) {
+        navigation.send(UiNavigation::Activate);
+    }
+}
+
+/// Squashes a button for a moment, to acknowledge a press.
+fn press_animation() -> Tween<Scale> {
+    Tween::to(Vec3::splat(0.92), 0.05)
+        .with_ease(Ease::QuadOut)
+        .then(Vec3::ONE, 0.1)
+        .unscaled()
+}
+
+fn button_interaction(
+    mut commands: Commands,
+    mut focus: ResMut<UiFocus>,
+    mut pressed: EventWriter<ButtonPressed>,
+    q_buttons: Query<(Entity, &Interaction, &UiButton, Option<&Disabled>), Changed<Interaction>>,
+) {
+    for (entity, interaction, button, disabled) in &q_buttons {
+        if disabled.is_some() {
+            continue;
+        }
+        match interaction {
+            Interaction::Clicked => {
+                focus.0 = Some(entity);
+                pressed.send(ButtonPressed(button.action));
+                commands.entity(entity).insert(press_animation());
+            }
+            Interaction::Hovered => focus.0 = Some(entity),
+            Interaction::None if focus.0 == Some(entity) => focus.0 = None,
+            Interaction::None => {}
+        }
+    }
+}
+
+fn navigate_focus(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut focus: ResMut<UiFocus>,
+    mut navigation: EventReader<UiNavigation>,
+    mut pressed: EventWriter<ButtonPressed>,
+    q_buttons: Query<(Entity, &GlobalTransform, &UiButton), Without<Disabled>>,
+) {
+    // On the board the same presses move the board focus, its buttons are only clicked.
+    if *state.current() == GameState::Game {
+        navigation.iter().count();
+        return;
+    }
+    if let Some(focused) = focus.0 {
+        if q_buttons.get(focused).is_err() {
+            focus.0 = None;
+        }
+    }
+    for event in navigation.iter() {
+        // Reading order: top to bottom, then left to right.
+        let mut buttons: Vec<_> = q_buttons
+            .iter()
+            .map(|(entity, transform, button)| (entity, transform.translation(), button.action))
+            .collect();
+        if buttons.is_empty() {
+            continue;
+        }
+        buttons.sort_by(|(_, a, _), (_, b, _)| {
+            b.y.partial_cmp(&a.y)
+                .unwrap()
+                .then(a.x.partial_cmp(&b.x).unwrap())
+        });
+        let current = focus
+            .0
+            .and_then(|focused| buttons.iter().position(|(entity, ..)| *entity == focused));
+        let count = buttons.len();
+        match (event, current) {
+            (UiNavigation::Activate, Some(index)) => {
+                pressed.send(ButtonPressed(buttons[index].2));
+                commands.entity(buttons[index].0).insert(press_animation());
+            }
+            (UiNavigation::Activate, None) => {}
+            (UiNavigation::Next, Some(index)) => focus.0 = Some(buttons[(index + 1) % count].0),
+            (UiNavigation::Previous, Some(index)) => {
+                focus.0 = Some(buttons[(index + count - 1) % count].0)
+            }
+            (UiNavigation::Next | UiNavigation::Previous, None) => focus.0 = Some(buttons[0].0),
+        }
+    }
+}
+
+fn button_colors(
+    theme: Res<Theme>,
+    focus: Res<UiFocus>,
+    mut q_buttons: Query<(Entity, &Interaction, &mut UiColor, Option<&Disabled>), With<UiButton>>,
+) {
+    for (entity, interaction, mut color, disabled) in &mut q_buttons {
+        let wanted = if disabled.is_some() {
+            theme.button_disabled
+        } else if *interaction == Interaction::Clicked {
+            theme.button_pressed
+        } else if *interaction == Interaction::Hovered || focus.0 == Some(entity) {
+            theme.button_hovered
+        } else {
+            theme.button
+        };
+        if color.0 != wanted {
+            color.0 = wanted;
+        }
+    }
+}